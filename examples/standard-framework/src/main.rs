@@ -21,18 +21,14 @@ impl EventHandler for Handler {
 }
 
 polecen::expand_command_here!((DateCommandArgs) date => {
-    duration: Option<Duration>;
+    duration: Duration = Duration::from_secs(0);
 });
 
 #[command]
 async fn date(ctx: &Context, message: &Message, args: Args) -> CommandResult {
     match read_args!(DateCommandArgs, args.raw_quoted(), ctx, [M] message) {
         Ok(args) => {
-            let time = if let Some(add) = args.duration {
-                SystemTime::now() + add
-            } else {
-                SystemTime::now()
-            };
+            let time = SystemTime::now() + args.duration;
             message
                 .channel_id
                 .say(
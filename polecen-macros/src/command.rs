@@ -0,0 +1,163 @@
+use convert_case::{Case, Casing};
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::quote;
+use syn::parse::{ParseStream, Parser};
+use syn::{bracketed, FnArg, ItemFn, LitStr, Pat, Path, ReturnType, Token};
+
+use crate::derive::{
+    argument_input,
+    description,
+    lit_str,
+    required_value,
+    unknown_key,
+    PolecenAttr,
+};
+use crate::generate_arguments;
+use crate::input::{CommandInput, ItemInput};
+
+/// Generate the arguments structure and the `Command` implementation of a handler.
+///
+/// The handler takes the context and the message, then the arguments of the command.
+/// For `calc`, the arguments are read by `CalcArgs` and the command is `CalcCommand`.
+pub(crate) fn generate_command_fn(attr: PolecenAttr, mut item: ItemFn) -> syn::Result<TokenStream> {
+    let fn_name = item.sig.ident.clone();
+    if item.sig.asyncness.is_none() {
+        return Err(syn::Error::new(fn_name.span(), "command handlers must be async"));
+    }
+    if !item.sig.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &item.sig.generics,
+            "command handlers can't be generic",
+        ));
+    }
+
+    let mut name = None;
+    let mut aliases = Vec::new();
+    let mut description = description(&item.attrs);
+    let mut args_name = None;
+    let mut on_error = None;
+    for (key, value) in attr.items {
+        match key.to_string().as_str() {
+            "name" => name = Some(lit_str(&key, value)?),
+            "aliases" => aliases = lit_str_list.parse2(required_value(&key, value)?)?,
+            "description" => description = Some(lit_str(&key, value)?),
+            "args" => args_name = Some(syn::parse2::<Ident>(required_value(&key, value)?)?),
+            "on_error" => on_error = Some(syn::parse2::<Path>(required_value(&key, value)?)?),
+            _ => {
+                return Err(unknown_key(
+                    &key,
+                    "`name`, `aliases`, `description`, `args` or `on_error`",
+                ))
+            },
+        }
+    }
+    let mut pattern = vec![name
+        .unwrap_or_else(|| LitStr::new(&fn_name.to_string().to_case(Case::Kebab), fn_name.span()))];
+    pattern.extend(aliases);
+
+    let mut inputs = item.sig.inputs.iter_mut();
+    if inputs.next().zip(inputs.next()).is_none() {
+        return Err(syn::Error::new(
+            fn_name.span(),
+            "command handlers take the context and the message before the arguments",
+        ));
+    }
+    let mut arguments = Vec::new();
+    let mut fields = Vec::new();
+    for input in inputs {
+        let input = match input {
+            FnArg::Typed(input) => input,
+            FnArg::Receiver(receiver) => {
+                return Err(syn::Error::new_spanned(receiver, "command handlers can't be methods"));
+            },
+        };
+        let field = match &*input.pat {
+            Pat::Ident(pat) => pat.ident.clone(),
+            pat => return Err(syn::Error::new_spanned(pat, "arguments must be identifiers")),
+        };
+        arguments.push(argument_input(&input.attrs, &field, &input.ty)?);
+        // options of the arguments are not valid attributes for parameters
+        input.attrs.retain(|attr| !attr.path.is_ident("polecen"));
+        fields.push(field);
+    }
+
+    let struct_prefix = fn_name.to_string().to_case(Case::Pascal);
+    let args_name =
+        args_name.unwrap_or_else(|| Ident::new(&format!("{}Args", struct_prefix), fn_name.span()));
+    let command_name = Ident::new(&format!("{}Command", struct_prefix), fn_name.span());
+    let vis = item.vis.clone();
+    let input = CommandInput::Command {
+        struct_name: args_name.clone(),
+        pattern,
+        description,
+        item: ItemInput { attrs: Vec::new(), vis: Some(vis.clone()) },
+        arguments,
+    };
+
+    let mut structs = Vec::new();
+    generate_arguments(
+        &mut structs,
+        &input,
+        None,
+        "",
+        true,
+        &Ident::new("ctx", Span::call_site()),
+        &Ident::new("args", Span::call_site()),
+    );
+    #[cfg(feature = "interactions")]
+    structs.push(crate::interactions::generate_application_command(&input, &args_name));
+
+    let context = quote!(::polecen::serenity::client::Context);
+    let message = quote!(::polecen::serenity::model::channel::Message);
+    let result = quote!(Result<(), ::polecen::command::CommandError>);
+    let call = match &item.sig.output {
+        ReturnType::Default => quote! {
+            #fn_name(ctx, message, #(#fields),*).await;
+            Ok(())
+        },
+        ReturnType::Type(..) => quote! {
+            #fn_name(ctx, message, #(#fields),*).await.map_err(Into::into)
+        },
+    };
+    let on_error = on_error.map(|on_error| {
+        quote! {
+            async fn on_error(
+                ctx: &#context,
+                message: &#message,
+                content: &str,
+                error: ::polecen::command::CommandArgumentsReadError,
+            ) -> #result {
+                #on_error(ctx, message, content, error).await
+            }
+        }
+    });
+    let doc = format!("Command running [`{}`].", fn_name);
+
+    Ok(quote! {
+        #item
+
+        #(#structs)*
+
+        #[doc = #doc]
+        #vis struct #command_name;
+
+        #[::polecen::async_trait]
+        impl ::polecen::command::Command for #command_name {
+            type Arguments = #args_name;
+
+            async fn run(ctx: &#context, message: &#message, args: #args_name) -> #result {
+                let #args_name { #(#fields),* } = args;
+                #call
+            }
+
+            #on_error
+        }
+    })
+}
+
+/// Parse `["a", "b"]`.
+fn lit_str_list(input: ParseStream) -> syn::Result<Vec<LitStr>> {
+    let content;
+    bracketed!(content in input);
+    Ok(content.parse_terminated::<LitStr, Token![,]>(|input| input.parse())?.into_iter().collect())
+}
@@ -0,0 +1,279 @@
+use convert_case::{Case, Casing};
+use proc_macro2::{Ident, Span, TokenStream, TokenTree};
+use quote::quote;
+use syn::parse::{Parse, ParseStream};
+use syn::{Attribute, Data, DeriveInput, Fields, Lit, LitChar, LitStr, Meta, Token, Type, Variant};
+
+use crate::generate_arguments;
+use crate::input::{ArgumentInput, CommandInput, ItemInput, MatchOptionsInput};
+
+/// Implement `CommandArguments` for a user-written struct or enum.
+///
+/// The type is converted to the same input as `expand_command_here!`,
+/// fields to arguments and variants to subcommands, then only the implementations
+/// and the handler trait of enums are generated.
+pub(crate) fn derive_command_arguments(input: DeriveInput) -> syn::Result<TokenStream> {
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(&input.generics, "generic types are not supported"));
+    }
+
+    let mut name = None;
+    let mut aliases = Vec::new();
+    let mut opts = MatchOptionsInput::default();
+    for (key, value) in polecen_attrs(&input.attrs)? {
+        match key.to_string().as_str() {
+            "name" => name = Some(lit_str(&key, value)?),
+            "alias" => aliases.push(lit_str(&key, value)?),
+            "ignore_case" => opts.ignore_case = no_value(&key, value)?,
+            "prefix" => opts.prefix = no_value(&key, value)?,
+            _ => return Err(unknown_key(&key, "`name`, `alias`, `ignore_case` or `prefix`")),
+        }
+    }
+    // the name may include the names of the parent commands, e.g. `test kick`
+    let full_name = match &name {
+        Some(name) => name.value(),
+        None => input.ident.to_string().to_case(Case::Kebab),
+    };
+    let (parent_path, own_name) = full_name.rsplit_once(' ').unwrap_or(("", &full_name));
+    let span = name.as_ref().map_or_else(|| input.ident.span(), LitStr::span);
+    let mut pattern = vec![LitStr::new(own_name, span)];
+    pattern.extend(aliases);
+
+    let struct_name = input.ident.clone();
+    let description = description(&input.attrs);
+    let command = match &input.data {
+        Data::Struct(data) => {
+            if opts.ignore_case || opts.prefix {
+                return Err(syn::Error::new(
+                    input.ident.span(),
+                    "match options are only allowed on enums",
+                ));
+            }
+            let arguments = match &data.fields {
+                Fields::Named(fields) => {
+                    let fields = fields.named.iter().map(|field| {
+                        let name = field.ident.as_ref().expect("named field");
+                        argument_input(&field.attrs, name, &field.ty)
+                    });
+                    fields.collect::<syn::Result<_>>()?
+                },
+                Fields::Unit => Vec::new(),
+                Fields::Unnamed(fields) => {
+                    return Err(syn::Error::new_spanned(
+                        fields,
+                        "arguments must be named fields, tuple structs are not supported",
+                    ));
+                },
+            };
+            CommandInput::Command {
+                struct_name,
+                pattern,
+                description,
+                item: ItemInput::default(),
+                arguments,
+            }
+        },
+        Data::Enum(data) => {
+            let children =
+                data.variants.iter().map(subcommand_input).collect::<syn::Result<_>>()?;
+            // the handler trait has the visibility of the enum
+            CommandInput::CommandParent {
+                struct_name,
+                pattern,
+                description,
+                item: ItemInput { attrs: Vec::new(), vis: Some(input.vis.clone()) },
+                opts,
+                children,
+            }
+        },
+        Data::Union(data) => {
+            return Err(syn::Error::new(data.union_token.span, "unions are not supported"));
+        },
+    };
+
+    let mut structs = Vec::new();
+    let struct_name = generate_arguments(
+        &mut structs,
+        &command,
+        None,
+        parent_path,
+        false,
+        &Ident::new("ctx", Span::call_site()),
+        &Ident::new("args", Span::call_site()),
+    );
+    #[cfg(feature = "interactions")]
+    structs.push(crate::interactions::generate_application_command(&command, &struct_name));
+    #[cfg(not(feature = "interactions"))]
+    let _ = struct_name;
+
+    Ok(quote! { #(#structs)* })
+}
+
+/// Convert a field (or a parameter) to an argument, by writing it the way it would be
+/// declared in `expand_command_here!` so both are validated the same way.
+pub(crate) fn argument_input(
+    attrs: &[Attribute],
+    name: &Ident,
+    ty: &Type,
+) -> syn::Result<ArgumentInput> {
+    let mut named = None;
+    let mut description = description(attrs);
+    let mut short = None;
+    let mut opts = None;
+    let mut default = None;
+    for (key, value) in polecen_attrs(attrs)? {
+        match key.to_string().as_str() {
+            "named" => {
+                no_value(&key, value)?;
+                named = Some(quote!(--));
+            },
+            "short" => {
+                let lit: LitChar = syn::parse2(required_value(&key, value)?)?;
+                if !lit.value().is_ascii_alphabetic() {
+                    return Err(syn::Error::new(lit.span(), "short names must be a letter"));
+                }
+                let short_name = Ident::new(&lit.value().to_string(), lit.span());
+                short = Some(quote!(| - #short_name));
+            },
+            "span" => {
+                let span = required_value(&key, value)?;
+                opts = Some(quote!([#span]));
+            },
+            "default" => {
+                let value = required_value(&key, value)?;
+                default = Some(quote!(= #value));
+            },
+            "description" => description = Some(lit_str(&key, value)?),
+            _ => {
+                return Err(unknown_key(
+                    &key,
+                    "`named`, `short`, `span`, `default` or `description`",
+                ))
+            },
+        }
+    }
+    if short.is_some() && named.is_none() {
+        return Err(syn::Error::new(name.span(), "only named arguments can have a short name"));
+    }
+    let description = description.map(|description| quote!(, #description));
+
+    syn::parse2(quote! { #named #name #short: #ty #opts #default #description })
+}
+
+/// Convert a variant to a subcommand, either a unit variant or a variant
+/// wrapping a type implementing `CommandArguments`.
+fn subcommand_input(variant: &Variant) -> syn::Result<CommandInput> {
+    let mut name = None;
+    let mut aliases = Vec::new();
+    for (key, value) in polecen_attrs(&variant.attrs)? {
+        match key.to_string().as_str() {
+            "name" => name = Some(lit_str(&key, value)?),
+            "alias" => aliases.push(lit_str(&key, value)?),
+            _ => return Err(unknown_key(&key, "`name` or `alias`")),
+        }
+    }
+    let name = name.unwrap_or_else(|| {
+        LitStr::new(&variant.ident.to_string().to_case(Case::Kebab), variant.ident.span())
+    });
+    let mut pattern = vec![name];
+    pattern.extend(aliases);
+
+    let struct_name = variant.ident.clone();
+    let description = description(&variant.attrs);
+    match &variant.fields {
+        Fields::Unit => Ok(CommandInput::Command {
+            struct_name,
+            pattern,
+            description,
+            item: ItemInput::default(),
+            arguments: Vec::new(),
+        }),
+        Fields::Unnamed(fields) if fields.unnamed.len() == 1 => {
+            let ty = Box::new(fields.unnamed[0].ty.clone());
+            Ok(CommandInput::External { struct_name, pattern, description, ty })
+        },
+        _ => Err(syn::Error::new(
+            variant.ident.span(),
+            "subcommands must be unit variants or wrap a single type implementing `CommandArguments`",
+        )),
+    }
+}
+
+/// Description from doc comments, lines are joined with a space.
+pub(crate) fn description(attrs: &[Attribute]) -> Option<LitStr> {
+    let mut span = None;
+    let mut lines = Vec::new();
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident("doc")) {
+        if let Ok(Meta::NameValue(meta)) = attr.parse_meta() {
+            if let Lit::Str(lit) = meta.lit {
+                span.get_or_insert(lit.span());
+                lines.push(lit.value().trim().to_owned());
+            }
+        }
+    }
+    let description = lines.into_iter().filter(|line| !line.is_empty()).collect::<Vec<_>>();
+    if description.is_empty() {
+        return None;
+    }
+    Some(LitStr::new(&description.join(" "), span.unwrap_or_else(Span::call_site)))
+}
+
+/// Items of the `#[polecen(..)]` attributes, e.g. `#[polecen(name = "ver", ignore_case)]`.
+pub(crate) fn polecen_attrs(attrs: &[Attribute]) -> syn::Result<Vec<(Ident, Option<TokenStream>)>> {
+    let mut items = Vec::new();
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident("polecen")) {
+        items.extend(attr.parse_args::<PolecenAttr>()?.items);
+    }
+    Ok(items)
+}
+
+/// Comma-separated `key` or `key = value` items, values are kept as tokens.
+pub(crate) struct PolecenAttr {
+    pub items: Vec<(Ident, Option<TokenStream>)>,
+}
+
+impl Parse for PolecenAttr {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut items = Vec::new();
+        while !input.is_empty() {
+            let key: Ident = input.parse()?;
+            let value = if input.peek(Token![=]) {
+                input.parse::<Token![=]>()?;
+                let mut value = TokenStream::new();
+                while !input.is_empty() && !input.peek(Token![,]) {
+                    value.extend(Some(input.parse::<TokenTree>()?));
+                }
+                Some(value)
+            } else {
+                None
+            };
+            items.push((key, value));
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+        Ok(Self { items })
+    }
+}
+
+pub(crate) fn lit_str(key: &Ident, value: Option<TokenStream>) -> syn::Result<LitStr> {
+    syn::parse2(required_value(key, value)?)
+}
+
+pub(crate) fn required_value(key: &Ident, value: Option<TokenStream>) -> syn::Result<TokenStream> {
+    value.ok_or_else(|| syn::Error::new(key.span(), format!("`{}` requires a value", key)))
+}
+
+/// Check a flag item doesn't have a value, returns true to set the flag.
+pub(crate) fn no_value(key: &Ident, value: Option<TokenStream>) -> syn::Result<bool> {
+    match value {
+        Some(value) => {
+            Err(syn::Error::new_spanned(value, format!("`{}` doesn't take a value", key)))
+        },
+        None => Ok(true),
+    }
+}
+
+pub(crate) fn unknown_key(key: &Ident, expected: &str) -> syn::Error {
+    syn::Error::new(key.span(), format!("unknown polecen option, expected {}", expected))
+}
@@ -0,0 +1,131 @@
+use convert_case::{Case, Casing};
+use proc_macro2::{Ident, TokenStream};
+use quote::quote;
+
+use crate::input::CommandInput;
+
+/// Generate the handler trait of a parent, with one method per child,
+/// and the functions routing the arguments to it, also used by the router.
+///
+/// For `PolecenCommandArgs`, the trait is `PolecenCommandArgsHandler`.
+///
+/// # Arguments
+///
+/// * `children` - Children of the parent
+/// * `children_structs` - Generated structures of the children, None for unit variants
+/// * `parent_name` - Name of the generated enum
+/// * `vis` - Visibility of the generated enum
+pub(crate) fn generate_handler(
+    children: &[CommandInput],
+    children_structs: &[Option<TokenStream>],
+    parent_name: &Ident,
+    vis: &TokenStream,
+) -> TokenStream {
+    let trait_name = Ident::new(&format!("{}Handler", parent_name), parent_name.span());
+    let context = quote!(::polecen::serenity::client::Context);
+    let message = quote!(::polecen::serenity::model::channel::Message);
+    let result = quote!(Result<(), ::polecen::command::CommandError>);
+    let strategy = quote!(::polecen::arguments::resolve::ResolveStrategy);
+
+    let mut methods = Vec::new();
+    let mut arms = Vec::new();
+    for (child, child_struct) in children.iter().zip(children_structs) {
+        let variant = child.struct_name();
+        let method = Ident::new(&variant.to_string().to_case(Case::Snake), variant.span());
+        let doc = format!("Handle `{}`.", child.command_pattern()[0].value());
+        if let Some(child_struct) = child_struct {
+            methods.push(quote! {
+                #[doc = #doc]
+                async fn #method(
+                    &self,
+                    ctx: &#context,
+                    message: &#message,
+                    args: #child_struct,
+                ) -> #result;
+            });
+            arms.push(quote! {
+                Self::#variant(args) => handler.#method(ctx, message, args).await,
+            });
+        } else {
+            methods.push(quote! {
+                #[doc = #doc]
+                async fn #method(&self, ctx: &#context, message: &#message) -> #result;
+            });
+            arms.push(quote! {
+                Self::#variant => handler.#method(ctx, message).await,
+            });
+        }
+    }
+
+    let trait_doc = format!("Handler of the subcommands of [`{}`].", parent_name);
+    quote! {
+        #[doc = #trait_doc]
+        #[::polecen::async_trait]
+        #vis trait #trait_name: Send + Sync {
+            #(#methods)*
+
+            /// Handle arguments which can't be read, `content` is the one given to `dispatch`.
+            ///
+            /// Defaults to returning the error.
+            async fn on_error(
+                &self,
+                _ctx: &#context,
+                _message: &#message,
+                _content: &str,
+                error: ::polecen::command::CommandArgumentsReadError,
+            ) -> #result {
+                Err(Box::new(error))
+            }
+        }
+
+        impl #parent_name {
+            /// Call the method of `handler` matching the subcommand.
+            #vis async fn route<H: #trait_name + ?Sized>(
+                self,
+                handler: &H,
+                ctx: &#context,
+                message: &#message,
+            ) -> #result {
+                match self {
+                    #(#arms)*
+                }
+            }
+
+            /// Read the arguments from `content`, the part of the message content after the
+            /// command name, resolving entities following `strategy`, then route them to `handler`.
+            #vis async fn dispatch<H: #trait_name + ?Sized>(
+                handler: &H,
+                ctx: &#context,
+                message: &#message,
+                content: &str,
+                strategy: #strategy,
+            ) -> #result {
+                let tokens = ::polecen::arguments::tokenize::tokenize_spanned(content);
+                let args = tokens
+                    .iter()
+                    .map(|token| ::polecen::arguments::tokenize::ArgumentToken::with_source(token, content));
+                let parse_ctx = ::polecen::arguments::parse::ArgumentParseContext::from_message(ctx, message)
+                    .with_strategy(strategy);
+                match <Self as ::polecen::command::CommandArguments>::read_arguments(args, 0, parse_ctx).await {
+                    Ok(args) => args.route(handler, ctx, message).await,
+                    Err(error) => handler.on_error(ctx, message, content, error).await,
+                }
+            }
+        }
+
+        #[::polecen::async_trait]
+        impl ::polecen::router::Group for #parent_name {
+            type Handler = dyn #trait_name;
+
+            async fn dispatch(
+                handler: &Self::Handler,
+                ctx: &#context,
+                message: &#message,
+                content: &str,
+                strategy: #strategy,
+            ) -> #result {
+                #parent_name::dispatch(handler, ctx, message, content, strategy).await
+            }
+        }
+    }
+}
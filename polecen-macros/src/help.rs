@@ -0,0 +1,150 @@
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::LitStr;
+
+use crate::input::{ArgumentDefault, ArgumentInput, ArgumentSpan, CommandInput};
+use crate::utils::tokens_to_string;
+
+/// Generate `usage` and `help` functions of a generated structure.
+///
+/// # Arguments
+///
+/// * `input` - Command input
+/// * `path` - Full name of the command, including parent commands
+/// * `children` - Generated structures of the children, if `input` is a parent
+pub(crate) fn generate_help_fns(
+    input: &CommandInput,
+    path: &str,
+    children: &[Option<TokenStream>],
+) -> TokenStream {
+    let usage_lit = LitStr::new(&usage(input, path), Span::call_site());
+    let help = match input {
+        CommandInput::CommandParent { children: children_input, .. } => {
+            let subcommands = children_input.iter().zip(children).map(|(child, child_struct)| {
+                if let CommandInput::External { ty, .. } = child {
+                    // the variant decides the name the subcommand is invoked with
+                    let pattern = child.command_pattern();
+                    let (name, aliases) = pattern.split_first().unwrap();
+                    let description = child.description().map(|description| {
+                        quote! { help.description = Some(#description); }
+                    });
+                    quote! {
+                        {
+                            let mut help = <#ty as ::polecen::command::CommandArguments>::help();
+                            help.name = #name;
+                            help.aliases = vec![#(#aliases),*];
+                            #description
+                            help
+                        }
+                    }
+                } else if let Some(child_struct) = child_struct {
+                    quote! { <#child_struct as ::polecen::command::CommandArguments>::help() }
+                } else {
+                    let child_path = format!("{} {}", path, child.command_pattern()[0].value());
+                    let child_usage = LitStr::new(&usage(child, &child_path), Span::call_site());
+                    command_help(child, &child_usage, quote!(Vec::new()))
+                }
+            });
+            command_help(input, &usage_lit, quote!(vec![#(#subcommands),*]))
+        },
+        _ => command_help(input, &usage_lit, quote!(Vec::new())),
+    };
+
+    quote! {
+        fn usage() -> &'static str {
+            #usage_lit
+        }
+
+        fn help() -> ::polecen::help::CommandHelp {
+            #help
+        }
+    }
+}
+
+fn command_help(input: &CommandInput, usage: &LitStr, subcommands: TokenStream) -> TokenStream {
+    let pattern = input.command_pattern();
+    let (name, aliases) = pattern.split_first().unwrap();
+    let description = option(input.description());
+    let arguments = match input {
+        CommandInput::Command { arguments, .. } => arguments.iter().map(argument_help).collect(),
+        _ => Vec::new(),
+    };
+    quote! {
+        ::polecen::help::CommandHelp {
+            name: #name,
+            aliases: vec![#(#aliases),*],
+            description: #description,
+            usage: #usage,
+            arguments: vec![#(#arguments),*],
+            subcommands: #subcommands,
+        }
+    }
+}
+
+fn argument_help(argument: &ArgumentInput) -> TokenStream {
+    let ArgumentInput { name, ty, required, named, default, description, .. } = argument;
+    let name = match named {
+        Some(named) => LitStr::new(&named.names().join(", "), name.span()),
+        None => LitStr::new(&name.to_string(), name.span()),
+    };
+    let default = option(default.as_ref().map(|default| {
+        let default = match default {
+            ArgumentDefault::Value(expr) => tokens_to_string(expr),
+            ArgumentDefault::Author => "@author".to_owned(),
+            ArgumentDefault::Channel => "@channel".to_owned(),
+        };
+        LitStr::new(&default, name.span())
+    }));
+    let description = option(description.clone());
+    quote! {
+        ::polecen::help::ArgumentHelp {
+            name: #name,
+            type_name: <#ty as ::polecen::arguments::parse::ArgumentType>::type_name(),
+            description: #description,
+            required: #required,
+            default: #default,
+        }
+    }
+}
+
+fn option(lit: Option<LitStr>) -> TokenStream {
+    match lit {
+        Some(lit) => quote!(Some(#lit)),
+        None => quote!(None),
+    }
+}
+
+/// Usage of a command, e.g. `test kick <target> [reason...]`.
+fn usage(input: &CommandInput, path: &str) -> String {
+    let mut usage = path.to_owned();
+    match input {
+        CommandInput::CommandParent { children, .. } => {
+            let names =
+                children.iter().map(|child| child.command_pattern()[0].value()).collect::<Vec<_>>();
+            usage.push_str(&format!(" <{}>", names.join("|")));
+        },
+        CommandInput::External { .. } => {},
+        CommandInput::Command { arguments, .. } => {
+            for argument in arguments {
+                if let Some(named) = &argument.named {
+                    if named.flag {
+                        usage.push_str(&format!(" [--{}]", named.long));
+                    } else {
+                        usage.push_str(&format!(" [--{0} <{0}>]", named.long));
+                    }
+                    continue;
+                }
+                let mut name = argument.name.to_string();
+                if argument.span() != ArgumentSpan::Fixed(1) {
+                    name.push_str("...");
+                }
+                if argument.required {
+                    usage.push_str(&format!(" <{}>", name));
+                } else {
+                    usage.push_str(&format!(" [{}]", name));
+                }
+            }
+        },
+    }
+    usage
+}
@@ -1,23 +1,35 @@
-use convert_case::Case;
-use proc_macro2::Span;
+use convert_case::{Case, Casing};
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
 use syn::parse::{Parse, ParseStream};
-use syn::{braced, bracketed, parenthesized, Ident, LitInt, LitStr, Token, Type};
+use syn::{
+    braced,
+    bracketed,
+    parenthesized,
+    Attribute,
+    Expr,
+    Ident,
+    LitInt,
+    LitStr,
+    Token,
+    Type,
+    Visibility,
+};
 
 use crate::utils::ConvertCase;
 
 mod kw {
     syn::custom_keyword!(Option);
+    syn::custom_keyword!(Vec);
 }
 
 macro_rules! optional_wrapped {
     ($input:ident, $wrapper:ident) => {
-        if let Some(opt_input) = (|| {
+        if let Ok(opt_input) = (|| {
             let content;
             $wrapper!(content in $input);
             Ok(content)
-        })()
-        .ok()
-        {
+        })() {
             Some(opt_input.parse()?)
         } else {
             None
@@ -36,8 +48,29 @@ macro_rules! optional {
 }
 
 pub(crate) enum CommandInput {
-    CommandParent { struct_name: Ident, pattern: Vec<LitStr>, children: Vec<CommandInput> },
-    Command { struct_name: Ident, pattern: Vec<LitStr>, arguments: Vec<ArgumentInput> },
+    CommandParent {
+        struct_name: Ident,
+        pattern: Vec<LitStr>,
+        description: Option<LitStr>,
+        item: ItemInput,
+        opts: MatchOptionsInput,
+        children: Vec<CommandInput>,
+    },
+    Command {
+        struct_name: Ident,
+        pattern: Vec<LitStr>,
+        description: Option<LitStr>,
+        item: ItemInput,
+        arguments: Vec<ArgumentInput>,
+    },
+    /// Subcommand read by an existing type, only from `#[derive(CommandArguments)]`.
+    External {
+        /// Name of the variant.
+        struct_name: Ident,
+        pattern: Vec<LitStr>,
+        description: Option<LitStr>,
+        ty: Box<Type>,
+    },
 }
 
 impl CommandInput {
@@ -45,6 +78,7 @@ impl CommandInput {
         match self {
             Self::CommandParent { struct_name, .. } => struct_name.clone(),
             Self::Command { struct_name, .. } => struct_name.clone(),
+            Self::External { struct_name, .. } => struct_name.clone(),
         }
     }
 
@@ -52,12 +86,51 @@ impl CommandInput {
         match self {
             Self::CommandParent { pattern, .. } => pattern.clone(),
             Self::Command { pattern, .. } => pattern.clone(),
+            Self::External { pattern, .. } => pattern.clone(),
+        }
+    }
+
+    pub fn description(&self) -> Option<LitStr> {
+        match self {
+            Self::CommandParent { description, .. } => description.clone(),
+            Self::Command { description, .. } => description.clone(),
+            Self::External { description, .. } => description.clone(),
+        }
+    }
+
+    /// Attributes and visibility of the generated type, `None` for external commands.
+    pub fn item(&self) -> Option<&ItemInput> {
+        match self {
+            Self::CommandParent { item, .. } => Some(item),
+            Self::Command { item, .. } => Some(item),
+            Self::External { .. } => None,
+        }
+    }
+
+    /// Add the derives and the visibility of a parent to this command and its children,
+    /// so the generated types can be used together.
+    fn inherit(&mut self, parent: &ItemInput) {
+        let item = match self {
+            Self::CommandParent { item, children, .. } => {
+                for child in children {
+                    child.inherit(parent);
+                }
+                item
+            },
+            Self::Command { item, .. } => item,
+            Self::External { .. } => return,
+        };
+        let derives = parent.attrs.iter().filter(|attr| attr.path.is_ident("derive")).cloned();
+        item.attrs.splice(0..0, derives);
+        if item.vis.is_none() {
+            item.vis = parent.vis.clone();
         }
     }
 }
 
 impl Parse for CommandInput {
     fn parse(input: ParseStream) -> syn::Result<Self> {
+        let item: ItemInput = input.parse()?;
         let struct_name: Option<Ident> = optional_wrapped!(input, parenthesized);
         let name: Ident = input.parse()?;
         let struct_name = if let Some(struct_name) = struct_name {
@@ -78,65 +151,385 @@ impl Parse for CommandInput {
             }
         }
 
+        let description = optional!(input);
         input.parse::<Token![=>]>()?;
         let is_parent = input.peek(Token![match]);
+        let mut opts = None;
         if is_parent {
             input.parse::<Token![match]>()?;
+            opts = optional_wrapped!(input, bracketed);
         }
 
         let content;
         braced!(content in input);
         if is_parent {
-            let children = content
+            let mut children = content
                 .parse_terminated::<CommandInput, Token![,]>(Self::parse)?
                 .into_iter()
-                .collect();
-            Ok(Self::CommandParent { struct_name, pattern, children })
+                .collect::<Vec<_>>();
+            for child in &mut children {
+                child.inherit(&item);
+            }
+            let opts = opts.unwrap_or_default();
+            Ok(Self::CommandParent { struct_name, pattern, description, item, opts, children })
         } else {
-            let arguments = content
+            let arguments: Vec<ArgumentInput> = content
                 .parse_terminated::<ArgumentInput, Token![;]>(ArgumentInput::parse)?
                 .into_iter()
                 .collect();
-            Ok(Self::Command { struct_name, pattern, arguments })
+            let positional = arguments.iter().filter(|arg| arg.named.is_none()).collect::<Vec<_>>();
+            if let Some((_, init)) = positional.split_last() {
+                if let Some(greedy) = init.iter().find(|arg| arg.span() == ArgumentSpan::Rest) {
+                    return Err(syn::Error::new(
+                        greedy.name.span(),
+                        "only the last argument can consume the rest of the message",
+                    ));
+                }
+            }
+            Ok(Self::Command { struct_name, pattern, description, item, arguments })
         }
     }
 }
 
+/// Attributes and visibility of a generated type, e.g. `#[derive(Hash)] pub(crate) (Args) test`.
+///
+/// Derives of a parent also apply to its children, as does its visibility
+/// unless the child has its own.
+#[derive(Default)]
+pub(crate) struct ItemInput {
+    pub attrs: Vec<Attribute>,
+    /// Visibility, `pub` if not given.
+    pub vis: Option<Visibility>,
+}
+
+impl Parse for ItemInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let attrs = input.call(Attribute::parse_outer)?;
+        let vis = match input.parse()? {
+            Visibility::Inherited => None,
+            vis => Some(vis),
+        };
+        Ok(Self { attrs, vis })
+    }
+}
+
+/// Options of the subcommand matching of a parent, e.g. `match [ignore_case, prefix] { ... }`.
+#[derive(Default)]
+pub(crate) struct MatchOptionsInput {
+    /// Match subcommand names regardless of case.
+    pub ignore_case: bool,
+    /// Accept unambiguous prefixes of subcommand names.
+    pub prefix: bool,
+}
+
+impl Parse for MatchOptionsInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut opts = Self::default();
+        for option in input.parse_terminated::<Ident, Token![,]>(Ident::parse)? {
+            match option.to_string().as_str() {
+                "ignore_case" => opts.ignore_case = true,
+                "prefix" => opts.prefix = true,
+                _ => {
+                    return Err(syn::Error::new(
+                        option.span(),
+                        "unknown match option, expected `ignore_case` or `prefix`",
+                    ))
+                },
+            }
+        }
+        Ok(opts)
+    }
+}
+
 pub(crate) struct ArgumentInput {
     pub name: Ident,
     pub ty: Type,
+    /// Whether the argument must be given, false for optional arguments, flags
+    /// and arguments with a default value.
     pub required: bool,
+    /// Whether the argument is declared as `Option<T>`.
+    pub optional: bool,
+    /// Number of values if the argument is declared as `Vec<T>`, `ty` is then the type of a value.
+    pub variadic: Option<VariadicInput>,
+    pub default: Option<ArgumentDefault>,
+    pub named: Option<NamedArgumentInput>,
     pub opts: Option<ArgumentOptionsInput>,
     pub description: Option<LitStr>,
 }
 
 impl Parse for ArgumentInput {
     fn parse(input: ParseStream) -> syn::Result<Self> {
-        let name = input.parse()?;
+        let is_named = input.peek(Token![-]);
+        if is_named {
+            input.parse::<Token![-]>()?;
+            input.parse::<Token![-]>()?;
+        }
+        let name: Ident = input.parse()?;
+        let short = if is_named && input.peek(Token![|]) {
+            input.parse::<Token![|]>()?;
+            input.parse::<Token![-]>()?;
+            let short: Ident = input.parse()?;
+            let mut chars = short.to_string().chars().collect::<Vec<_>>();
+            if chars.len() != 1 {
+                return Err(syn::Error::new(
+                    short.span(),
+                    "short names must be a single character",
+                ));
+            }
+            chars.pop()
+        } else {
+            None
+        };
         input.parse::<Token![:]>()?;
 
-        let (ty, optional) = if input.peek(kw::Option) {
+        let (ty, optional, is_vec) = if input.peek(kw::Option) {
             input.parse::<kw::Option>()?;
             input.parse::<Token![<]>()?;
+            if input.peek(kw::Vec) {
+                return Err(syn::Error::new(
+                    name.span(),
+                    "lists of values which may be empty are declared as `Vec<T> [..]`",
+                ));
+            }
+            let ty = input.parse()?;
+            input.parse::<Token![>]>()?;
+            (ty, true, false)
+        } else if input.peek(kw::Vec) && input.peek2(Token![<]) {
+            input.parse::<kw::Vec>()?;
+            input.parse::<Token![<]>()?;
             let ty = input.parse()?;
             input.parse::<Token![>]>()?;
-            (ty, true)
+            (ty, false, true)
+        } else {
+            (input.parse()?, false, false)
+        };
+
+        let opts: Option<ArgumentOptionsInput> = optional_wrapped!(input, bracketed);
+        let variadic = if is_vec {
+            if is_named {
+                return Err(syn::Error::new(name.span(), "named arguments can't be `Vec<T>`"));
+            }
+            Some(VariadicInput::from_opts(opts.as_ref(), &name)?)
+        } else {
+            if opts.as_ref().is_some_and(|opts| opts.range.is_some()) {
+                return Err(syn::Error::new(
+                    name.span(),
+                    "only `Vec<T>` arguments can have a range of values",
+                ));
+            }
+            None
+        };
+        let default = if input.peek(Token![=]) {
+            input.parse::<Token![=]>()?;
+            if optional {
+                return Err(syn::Error::new(
+                    name.span(),
+                    "optional arguments can't have a default value",
+                ));
+            }
+            Some(input.parse()?)
         } else {
-            (input.parse()?, false)
+            None
+        };
+        let named = if is_named {
+            let flag = default.is_none()
+                && matches!(&ty, Type::Path(path) if !optional && path.path.is_ident("bool"));
+            if !optional && !flag && default.is_none() {
+                return Err(syn::Error::new(
+                    name.span(),
+                    "named arguments must either be `bool` flags, `Option<T>` or have a default value",
+                ));
+            }
+            if opts.as_ref().is_some_and(|opts| opts.span.is_some()) {
+                return Err(syn::Error::new(name.span(), "named arguments can't have a span"));
+            }
+            let long = name.to_string().to_case(Case::Kebab);
+            Some(NamedArgumentInput { long, short, flag })
+        } else {
+            None
         };
+        let is_flag = named.as_ref().is_some_and(|named| named.flag);
+        let accepts_none = variadic.as_ref().is_some_and(|variadic| variadic.min == 0);
+        Ok(ArgumentInput {
+            name,
+            ty,
+            required: !optional && default.is_none() && !is_flag && !accepts_none,
+            optional,
+            variadic,
+            default,
+            named,
+            opts,
+            description: optional!(input),
+        })
+    }
+}
+
+/// Value of an argument when it is not given.
+pub(crate) enum ArgumentDefault {
+    /// Any expression of the argument type, e.g. `Duration::from_secs(60)`.
+    Value(Box<Expr>),
+    /// The invoking author (`@author`), parsed as the argument type.
+    Author,
+    /// The channel the command was invoked in (`@channel`), parsed as the argument type.
+    Channel,
+}
 
-        let opts = optional_wrapped!(input, bracketed);
-        Ok(ArgumentInput { name, ty, required: !optional, opts, description: optional!(input) })
+impl Parse for ArgumentDefault {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        if input.peek(Token![@]) {
+            input.parse::<Token![@]>()?;
+            let ident: Ident = input.parse()?;
+            match ident.to_string().as_str() {
+                "author" => Ok(Self::Author),
+                "channel" => Ok(Self::Channel),
+                _ => Err(syn::Error::new(
+                    ident.span(),
+                    "unknown context default, expected `@author` or `@channel`",
+                )),
+            }
+        } else {
+            Ok(Self::Value(Box::new(input.parse()?)))
+        }
+    }
+}
+
+/// Argument given by name (`--name`) instead of position.
+pub(crate) struct NamedArgumentInput {
+    /// Long name, without leading dashes.
+    pub long: String,
+    /// Short name, without leading dash.
+    pub short: Option<char>,
+    /// Whether the argument is a `bool` flag which doesn't take a value.
+    pub flag: bool,
+}
+
+impl NamedArgumentInput {
+    /// Names the argument can be given with, e.g. `--delete-days` and `-d`.
+    pub fn names(&self) -> Vec<String> {
+        let mut names = vec![format!("--{}", self.long)];
+        if let Some(short) = self.short {
+            names.push(format!("-{}", short));
+        }
+        names
+    }
+}
+
+impl ArgumentInput {
+    pub fn span(&self) -> ArgumentSpan {
+        if self.variadic.is_some() {
+            return ArgumentSpan::Rest;
+        }
+        self.opts.as_ref().and_then(|opts| opts.span).unwrap_or(ArgumentSpan::Fixed(1))
+    }
+
+    /// Type of the generated field, as declared.
+    pub fn field_type(&self) -> TokenStream {
+        let ty = &self.ty;
+        if self.optional {
+            quote!(Option<#ty>)
+        } else if self.variadic.is_some() {
+            quote!(Vec<#ty>)
+        } else {
+            quote!(#ty)
+        }
     }
 }
 
 pub(crate) struct ArgumentOptionsInput {
-    pub span: Option<LitInt>,
+    pub span: Option<ArgumentSpan>,
+    /// Bounds of a `min..max` or `min..=max` range, both included,
+    /// either of them may be omitted.
+    pub range: Option<(Option<u8>, Option<u8>)>,
 }
 
 impl Parse for ArgumentOptionsInput {
     fn parse(input: ParseStream) -> syn::Result<Self> {
-        let span = input.parse()?;
-        Ok(Self { span })
+        let min_span = input.span();
+        let min = parse_optional_u8(input)?;
+        if !input.peek(Token![..]) {
+            let span = match min {
+                Some(0) => {
+                    return Err(syn::Error::new(min_span, "argument span must be at least 1"));
+                },
+                Some(n) => Some(ArgumentSpan::Fixed(n)),
+                None => None,
+            };
+            return Ok(Self { span, range: None });
+        }
+
+        // bounds are read as in Rust, `min..max` excludes max and `min..=max` includes it
+        let max = if input.peek(Token![..=]) {
+            let dots = input.parse::<Token![..=]>()?;
+            match parse_optional_u8(input)? {
+                Some(max) => Some(max),
+                None => {
+                    return Err(syn::Error::new(
+                        dots.spans[0],
+                        "inclusive ranges must have an upper bound",
+                    ));
+                },
+            }
+        } else {
+            input.parse::<Token![..]>()?;
+            let max_span = input.span();
+            match parse_optional_u8(input)? {
+                Some(max) => Some(max.checked_sub(1).ok_or_else(|| {
+                    syn::Error::new(max_span, "the maximum number of values must be at least 1")
+                })?),
+                None => None,
+            }
+        };
+        if min.is_none() && max.is_none() {
+            Ok(Self { span: Some(ArgumentSpan::Rest), range: None })
+        } else {
+            Ok(Self { span: None, range: Some((min, max)) })
+        }
     }
 }
+
+fn parse_optional_u8(input: ParseStream) -> syn::Result<Option<u8>> {
+    if input.peek(LitInt) {
+        Ok(Some(input.parse::<LitInt>()?.base10_parse()?))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Number of values of a `Vec<T>` argument, e.g. `Vec<Member> [1..=5]`.
+///
+/// Without options at least one value is required, `[..]` accepts none,
+/// `[N]` exactly N and `[min..max]` from min to max values, max excluded as in Rust ranges.
+pub(crate) struct VariadicInput {
+    pub min: u8,
+    pub max: Option<u8>,
+}
+
+impl VariadicInput {
+    fn from_opts(opts: Option<&ArgumentOptionsInput>, name: &Ident) -> syn::Result<Self> {
+        let (min, max) = match opts {
+            Some(ArgumentOptionsInput { range: Some((min, max)), .. }) => (min.unwrap_or(0), *max),
+            Some(ArgumentOptionsInput { span: Some(ArgumentSpan::Fixed(n)), .. }) => (*n, Some(*n)),
+            Some(ArgumentOptionsInput { span: Some(ArgumentSpan::Rest), .. }) => (0, None),
+            _ => (1, None),
+        };
+        match max {
+            Some(0) => {
+                Err(syn::Error::new(name.span(), "the maximum number of values must be at least 1"))
+            },
+            Some(max) if max < min => Err(syn::Error::new(
+                name.span(),
+                "the minimum number of values can't exceed the maximum",
+            )),
+            _ => Ok(Self { min, max }),
+        }
+    }
+}
+
+/// Number of tokens consumed by an argument.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ArgumentSpan {
+    /// Exactly N tokens, read as the text typed from the first to the last.
+    Fixed(u8),
+    /// All remaining tokens, read as the text typed from the first to the last.
+    Rest,
+}
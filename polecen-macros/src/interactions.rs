@@ -0,0 +1,234 @@
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::quote;
+use syn::LitStr;
+
+use crate::input::{ArgumentInput, CommandInput};
+use crate::reader::{
+    join_fields,
+    map_parse_error,
+    missing_value,
+    parse_values,
+    wrap_value,
+    FieldValue,
+};
+
+/// Generate the `InteractionArguments` implementation of a generated structure.
+///
+/// # Arguments
+///
+/// * `input` - Command input
+/// * `path` - Full name of the command, reported in errors
+/// * `struct_name` - Name of the generated structure
+/// * `children` - Generated structures of the children, if `input` is a parent
+pub(crate) fn generate_interaction_impl(
+    input: &CommandInput,
+    path: &str,
+    struct_name: &Ident,
+    children: &[Option<TokenStream>],
+) -> TokenStream {
+    let command = LitStr::new(path, Span::call_site());
+    let json = quote!(::polecen::serde_json::json);
+    let option_type = quote!(::polecen::interactions::ApplicationCommandOptionType);
+    let err = quote!(::polecen::command::CommandArgumentsReadError);
+    let ctx = Ident::new("ctx", Span::call_site());
+
+    let (options, reader) = match input {
+        CommandInput::CommandParent { children: children_input, .. } => {
+            let known = children_input.iter().flat_map(CommandInput::command_pattern);
+            let children_arms = children_input.iter().zip(children).map(|(child, child_struct)| {
+                let child_name = child.struct_name();
+                let pattern = child.command_pattern();
+                if let Some(child_struct) = child_struct {
+                    quote! { #(#pattern)|* => {
+                        Self::#child_name(
+                            <#child_struct as ::polecen::interactions::InteractionArguments>
+                                ::read_interaction(&subcommand.options, position + 1, ctx).await?
+                        )
+                    } }
+                } else {
+                    quote! { #(#pattern)|* => { Self::#child_name } }
+                }
+            });
+            let reader = quote! {
+                if let Some(subcommand) = options.first() {
+                    match subcommand.name.as_str() {
+                        #(#children_arms),*
+                        s => {
+                            return Err(#err::UnknownSubcommand {
+                                position: position,
+                                span: None,
+                                command: String::from(#command),
+                                given: s.to_owned(),
+                                suggestions: ::polecen::command::suggestions(s, &[#(#known),*]),
+                            });
+                        },
+                    }
+                } else {
+                    return Err(#err::MissingSubcommand {
+                        position: position,
+                        command: String::from(#command),
+                    });
+                }
+            };
+
+            let options = children_input.iter().zip(children).map(|(child, child_struct)| {
+                let kind = match child {
+                    CommandInput::CommandParent { .. } => quote!(#option_type::SubCommandGroup),
+                    CommandInput::Command { .. } => quote!(#option_type::SubCommand),
+                    CommandInput::External { ty, .. } => quote! {
+                        if <#ty as ::polecen::interactions::InteractionArguments>::has_subcommands() {
+                            #option_type::SubCommandGroup
+                        } else {
+                            #option_type::SubCommand
+                        }
+                    },
+                };
+                let (name, description) = command_name(child);
+                let options = if let Some(child_struct) = child_struct {
+                    quote! {
+                        <#child_struct as ::polecen::interactions::InteractionArguments>
+                            ::application_command_options()
+                    }
+                } else {
+                    quote!(Vec::<::polecen::serde_json::Value>::new())
+                };
+                quote! {
+                    #json!({
+                        "type": #kind,
+                        "name": #name,
+                        "description": #description,
+                        "options": #options,
+                    })
+                }
+            });
+            (options.collect::<Vec<_>>(), reader)
+        },
+        CommandInput::Command { arguments, .. } => {
+            let mut options = Vec::new();
+            let mut fields = Vec::new();
+            for (i, argument) in arguments.iter().enumerate() {
+                let i = i as u8;
+                let ArgumentInput { name: field, ty, required, description, .. } = argument;
+                let name = LitStr::new(&field.to_string(), field.span());
+                let description = description.clone().unwrap_or_else(|| name.clone());
+                // lists of values are given as text, tokenized like message content
+                let kind = if argument.variadic.is_some() {
+                    quote!(#option_type::String)
+                } else {
+                    quote!(<#ty as ::polecen::arguments::parse::ArgumentType>::option_type())
+                };
+                options.push((*required, quote! {
+                    #json!({
+                        "type": #kind,
+                        "name": #name,
+                        "description": #description,
+                        "required": #required,
+                    })
+                }));
+
+                let parse = if argument.variadic.is_some() {
+                    let values = quote! {
+                        tokens
+                            .iter()
+                            .map(|value| (position + #i, None::<::std::ops::Range<usize>>, value.as_str()))
+                            .collect::<Vec<_>>()
+                    };
+                    let parse = parse_values(argument, path, &ctx, values, quote!(position + #i));
+                    quote! {
+                        {
+                            let raw = ::polecen::interactions::option_raw_value(option).unwrap_or_default();
+                            let tokens = ::polecen::arguments::tokenize::tokenize(&raw);
+                            #parse
+                        }
+                    }
+                } else {
+                    wrap_value(
+                        argument,
+                        map_parse_error(
+                            argument,
+                            path,
+                            quote! {
+                                <#ty as ::polecen::arguments::parse::ArgumentType>::parse_option(&ctx, option)
+                                    .await
+                            },
+                            quote!(position + #i),
+                            quote!(None),
+                            quote!(::polecen::interactions::option_raw_value(option)
+                                .unwrap_or_default()),
+                        ),
+                    )
+                };
+                let err_handler = missing_value(argument, path, quote!(position + #i), &ctx);
+                fields.push((
+                    field.clone(),
+                    FieldValue::Parsed(quote! {
+                        if let Some(option) = options.iter().find(|o| o.name == #name) {
+                            #parse
+                        } else {
+                            #err_handler
+                        }
+                    }),
+                ));
+            }
+            // Discord rejects required options after optional ones, options are
+            // matched by name so their order doesn't matter when reading
+            options.sort_by_key(|(required, _)| !required);
+            let options = options.into_iter().map(|(_, option)| option).collect();
+            (options, join_fields(fields, &ctx))
+        },
+        CommandInput::External { .. } => unreachable!("external commands are only children"),
+    };
+    let has_subcommands = matches!(input, CommandInput::CommandParent { .. });
+
+    quote! {
+        #[::polecen::async_trait]
+        impl ::polecen::interactions::InteractionArguments for #struct_name {
+            fn application_command_options() -> Vec<::polecen::serde_json::Value> {
+                vec![#(#options),*]
+            }
+
+            fn has_subcommands() -> bool {
+                #has_subcommands
+            }
+
+            async fn read_interaction<'a>(
+                options: &'a [::polecen::interactions::ApplicationCommandInteractionDataOption],
+                position: u8,
+                ctx: ::polecen::arguments::parse::ArgumentParseContext<'a>,
+            ) -> Result<Self, #err> {
+                Ok(#reader)
+            }
+        }
+    }
+}
+
+/// Generate the `application_command` function of a top-level structure.
+pub(crate) fn generate_application_command(
+    input: &CommandInput,
+    struct_name: &Ident,
+) -> TokenStream {
+    let (name, description) = command_name(input);
+    quote! {
+        impl #struct_name {
+            /// Application command definition, as expected by Discord API
+            /// to register a global or guild command.
+            pub fn application_command() -> ::polecen::serde_json::Value {
+                ::polecen::serde_json::json!({
+                    "name": #name,
+                    "description": #description,
+                    "options": <Self as ::polecen::interactions::InteractionArguments>
+                        ::application_command_options(),
+                })
+            }
+        }
+    }
+}
+
+/// Name and description of a command, aliases can't be registered so only
+/// the first element of the pattern is used. Discord requires a description,
+/// it defaults to the name.
+fn command_name(input: &CommandInput) -> (LitStr, LitStr) {
+    let name = input.command_pattern().remove(0);
+    let description = input.description().unwrap_or_else(|| name.clone());
+    (name, description)
+}
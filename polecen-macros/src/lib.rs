@@ -1,11 +1,19 @@
 use proc_macro::TokenStream as StdTokenStream;
 use proc_macro2::{Ident, Span, TokenStream};
 use quote::quote;
-use syn::{parse_macro_input, LitStr};
+use syn::{parse_macro_input, DeriveInput, ItemFn, LitStr};
 
-use crate::input::{ArgumentInput, CommandInput};
+use crate::derive::PolecenAttr;
+use crate::input::{CommandInput, ItemInput, MatchOptionsInput};
 
+mod command;
+mod derive;
+mod handler;
+mod help;
 mod input;
+#[cfg(feature = "interactions")]
+mod interactions;
+mod reader;
 mod utils;
 
 macro_rules! metavar {
@@ -19,10 +27,10 @@ macro_rules! metavar {
 
 macro_rules! ident {
     ($s:expr) => {
-        metavar!(Ident; $s);
+        metavar!(Ident; $s)
     };
     (MERGE $lhs:expr, $rhs:expr) => {
-        metavar!(Ident; MERGE $lhs, $rhs);
+        metavar!(Ident; MERGE $lhs, $rhs)
     };
 }
 
@@ -43,14 +51,96 @@ pub fn expand_command_here(tokens: StdTokenStream) -> StdTokenStream {
     let input = parse_macro_input!(tokens as CommandInput);
 
     let mut structs = Vec::new();
-    generate_arguments(&mut structs, &input, None, &ident!("ctx"), &ident!("args"));
+    let struct_name =
+        generate_arguments(&mut structs, &input, None, "", true, &ident!("ctx"), &ident!("args"));
     if structs.is_empty() {
         return StdTokenStream::new();
     }
+    #[cfg(feature = "interactions")]
+    structs.push(interactions::generate_application_command(&input, &struct_name));
+    #[cfg(not(feature = "interactions"))]
+    let _ = struct_name;
 
     (quote! { #(#structs)* }).into()
 }
 
+/// Implement `CommandArguments` for a struct or an enum, as an alternative
+/// to `expand_command_here!` when the types are written by hand.
+///
+/// The fields of a struct are its arguments, in order. The variants of an enum are its
+/// subcommands, either unit variants or variants wrapping a type implementing
+/// `CommandArguments`. Doc comments become descriptions.
+///
+/// Enums also get the handler trait of the enums of `expand_command_here!`, e.g.
+/// `TestArgsHandler` with the visibility of `TestArgs`, to be used with `Router::group`.
+///
+/// Options are given with `#[polecen(..)]`:
+/// * On the type: `name = "test kick"` (full name of the command, defaults to the name
+///   of the type in kebab case), `alias = "k"`, and `ignore_case`, `prefix` on enums.
+/// * On a field: `named`, `short = 'd'`, `span = ..` (what would be written between
+///   brackets in `expand_command_here!`), `default = 7` and `description = ".."`.
+/// * On a variant: `name = "version"` (defaults to the name of the variant in kebab case)
+///   and `alias = "ver"`.
+///
+/// ```ignore
+/// #[derive(polecen::CommandArguments)]
+/// #[polecen(name = "test")]
+/// enum TestArgs {
+///     /// Kick a member.
+///     Kick(KickArgs),
+///     #[polecen(alias = "ver")]
+///     Version,
+/// }
+///
+/// #[derive(polecen::CommandArguments)]
+/// #[polecen(name = "test kick")]
+/// struct KickArgs {
+///     target: Member,
+///     #[polecen(span = ..)]
+///     reason: Option<String>,
+/// }
+/// ```
+#[proc_macro_derive(CommandArguments, attributes(polecen))]
+pub fn derive_command_arguments(tokens: StdTokenStream) -> StdTokenStream {
+    let input = parse_macro_input!(tokens as DeriveInput);
+    derive::derive_command_arguments(input).unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
+/// Turn an async handler into a command, reading its arguments from its parameters.
+///
+/// The handler takes `&Context` and `&Message`, then the arguments of the command,
+/// and returns either nothing or a `Result<(), E>` where `E` converts to `CommandError`.
+/// For `calc`, `CalcArgs` reads the arguments and `CalcCommand` implements `polecen::command::Command`.
+///
+/// Options:
+/// * `name = "calc"` - Name of the command, defaults to the name of the function in kebab case,
+///   e.g. `user-info` for `user_info`, like the subcommands of `#[derive(CommandArguments)]`.
+/// * `aliases = ["c"]`
+/// * `description = ".."` - Description of the command, defaults to the doc comments.
+/// * `args = CalcArgs` - Name of the arguments structure.
+/// * `on_error = report` - Async function called with the context, the message, the content
+///   and the `CommandArgumentsReadError` when the arguments can't be read, returning a
+///   `Result<(), CommandError>`. By default the error is returned.
+///
+/// Parameters take the same `#[polecen(..)]` options as the fields of
+/// `#[derive(CommandArguments)]`.
+///
+/// ```ignore
+/// #[polecen::command(name = "calc", aliases = ["c"], on_error = report)]
+/// async fn calc(ctx: &Context, message: &Message, lhs: i32, op: Operator, rhs: i32) -> CommandResult {
+///     message.channel_id.say(&ctx.http, op.calc(lhs, rhs)).await?;
+///     Ok(())
+/// }
+///
+/// CalcCommand::invoke(&ctx, &message, "1 + 2", ResolveStrategy::default()).await?;
+/// ```
+#[proc_macro_attribute]
+pub fn command(attr: StdTokenStream, item: StdTokenStream) -> StdTokenStream {
+    let attr = parse_macro_input!(attr as PolecenAttr);
+    let item = parse_macro_input!(item as ItemFn);
+    command::generate_command_fn(attr, item).unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
 /// Generate argument structures and readers.
 /// Returns ident of top-level structure (input) name.
 ///
@@ -59,12 +149,16 @@ pub fn expand_command_here(tokens: StdTokenStream) -> StdTokenStream {
 /// * `structs` - Output, structures and parser impls are wrriten to this vector
 /// * `input` - Command input
 /// * `prefix` - Name of the upper-level input, if None this is a top-level call
+/// * `parent_path` - Full name of the upper-level command, empty for a top-level call
+/// * `define` - Whether to define the structure, false if it is written by the user
 /// * `ctx_ident` - Ident of the ctx variable in scope
 /// * `args_ident` - Ident of the args variable in scope
 pub(crate) fn generate_arguments(
     structs: &mut Vec<TokenStream>,
     input: &CommandInput,
     prefix: Option<String>,
+    parent_path: &str,
+    define: bool,
     ctx_ident: &Ident,
     args_ident: &Ident,
 ) -> Ident {
@@ -76,128 +170,160 @@ pub(crate) fn generate_arguments(
         ident!(&input.struct_name().to_string())
     };
 
+    let path = format!("{} {}", parent_path, input.command_pattern()[0].value());
+    let path = path.trim_start();
+    let command = LitStr::new(path, Span::call_site());
+
     let mut entries = Vec::new();
+    // generated structures of the children, None for unit variants
+    let mut children_structs = Vec::new();
     let reader = match input {
-        CommandInput::CommandParent { children, .. } => {
+        CommandInput::CommandParent { children, opts, .. } => {
+            // names and aliases of all children, for suggestions
+            let known = children.iter().flat_map(CommandInput::command_pattern).collect::<Vec<_>>();
             let mut children_arms = Vec::new();
             for child in children {
                 let child_name = child.struct_name();
                 let pattern = child.command_pattern();
-                if let CommandInput::Command { arguments, .. } = child {
-                    if arguments.is_empty() {
+                let child_struct = match child {
+                    CommandInput::Command { arguments, .. } if arguments.is_empty() => {
                         entries.push(quote! { #child_name });
                         children_arms.push(quote! { #(#pattern)|* => { Self::#child_name } });
+                        children_structs.push(None);
                         continue;
-                    }
-                }
-
-                let child_struct = generate_arguments(
-                    structs,
-                    child,
-                    Some(parent_name.to_string()),
-                    ctx_ident,
-                    args_ident,
-                );
+                    },
+                    CommandInput::External { ty, .. } => quote!(#ty),
+                    _ => {
+                        let child_struct = generate_arguments(
+                            structs,
+                            child,
+                            Some(parent_name.to_string()),
+                            path,
+                            true,
+                            ctx_ident,
+                            args_ident,
+                        );
+                        quote!(#child_struct)
+                    },
+                };
                 entries.push(quote! { #child_name(#child_struct) });
+                children_structs.push(Some(child_struct.clone()));
                 children_arms.push(quote! { #(#pattern)|* => {
-                    Self::#child_name(#child_struct::read_arguments(args, position + 1, ctx).await?)
+                    Self::#child_name(
+                        <#child_struct as ::polecen::command::CommandArguments>
+                            ::read_arguments(args, position + 1, ctx).await?
+                    )
                 } });
             }
 
+            // resolve the given name to a literal of the pattern of a child
+            let resolve = if opts.ignore_case || opts.prefix {
+                let MatchOptionsInput { ignore_case, prefix } = opts;
+                let children_patterns = children.iter().map(|child| {
+                    let pattern = child.command_pattern();
+                    quote! { &[#(#pattern),*] }
+                });
+                quote! {
+                    ::polecen::command::resolve_subcommand(
+                        subcommand.value,
+                        &[#(#children_patterns),*],
+                        #ignore_case,
+                        #prefix,
+                    )
+                    .map_err(|candidates| #err::AmbiguousSubcommand {
+                        position: position,
+                        span: subcommand.span.clone(),
+                        command: String::from(#command),
+                        given: subcommand.value.to_owned(),
+                        candidates,
+                    })?
+                }
+            } else {
+                quote! { subcommand.value }
+            };
+
             quote! {
                 if let Some(subcommand) = #args_ident.next() {
-                    match subcommand {
+                    match #resolve {
                         #(#children_arms),*
                         s => {
                             return Err(#err::UnknownSubcommand {
                                 position: position,
+                                span: subcommand.span,
+                                command: String::from(#command),
                                 given: s.to_owned(),
+                                suggestions: ::polecen::command::suggestions(s, &[#(#known),*]),
                             });
                         },
                     }
                 } else {
                     return Err(#err::MissingSubcommand {
                         position: position,
+                        command: String::from(#command),
                     });
                 }
             }
         },
         CommandInput::Command { arguments, .. } => {
-            let mut fields = Vec::new();
-            for (i, argument) in arguments.iter().enumerate() {
-                let i = i as u8;
-                let ArgumentInput { name: field, ty, required, .. } = &argument;
-                let required = *required;
-
-                entries.push(if required {
-                    quote! { pub #field: #ty }
-                } else {
-                    quote! { pub #field: Option<#ty> }
-                });
-
-                let inner_parse = quote! {
-                    #ty::parse_argument(
-                        &#ctx_ident,
-                        ::polecen::arguments::parse::ArgumentParseRaw {
-                            value: arg.to_owned(),
-                        },
-                    )
-                    .await
-                    .map_err(|e| #err::ValueParseError { position: position + #i, inner: e })?
-                };
-
-                let (parse, err_handler) = if required {
-                    let name = metavar!(LitStr; &argument.name.to_string());
-                    (inner_parse, quote! {
-                        return Err(#err::RequiredArgumentMissing {
-                            position: position + #i,
-                            name: String::from(#name),
-                        });
-                    })
-                } else {
-                    (quote! { Some(#inner_parse) }, quote! { None })
-                };
-                fields.push(quote! {
-                    #field: if let Some(arg) = #args_ident.next() {
-                        #parse
-                    } else {
-                        #err_handler
-                    }
-                });
-            }
-
-            quote! {
-                Self {
-                    #(#fields),*
-                }
+            for argument in arguments {
+                let field = &argument.name;
+                let ty = argument.field_type();
+                entries.push(quote! { pub #field: #ty });
             }
+            reader::generate_command_reader(arguments, path, ctx_ident, args_ident)
         },
+        CommandInput::External { .. } => unreachable!("external commands are only children"),
     };
 
-    let struct_type = match input {
-        CommandInput::CommandParent { .. } => quote! { enum },
-        CommandInput::Command { .. } => quote! { struct },
+    let help_fns = help::generate_help_fns(input, path, &children_structs);
+    let (attrs, vis) = match input.item() {
+        Some(ItemInput { attrs, vis: Some(vis) }) => (attrs.as_slice(), quote!(#vis)),
+        Some(ItemInput { attrs, vis: None }) => (attrs.as_slice(), quote!(pub)),
+        None => (&[][..], quote!(pub)),
     };
+    if define {
+        let struct_type = match input {
+            CommandInput::CommandParent { .. } => quote! { enum },
+            _ => quote! { struct },
+        };
+        structs.push(quote! {
+            #(#attrs)*
+            #[derive(Clone, Debug)]
+            #vis #struct_type #parent_name {
+                #(#entries),*
+            }
+        });
+    }
+    if let CommandInput::CommandParent { children, .. } = input {
+        structs.push(handler::generate_handler(children, &children_structs, &parent_name, &vis));
+    }
     structs.push(quote! {
-        #[derive(Clone, Debug)]
-        pub #struct_type #parent_name {
-            #(#entries),*
-        }
-
         #[::polecen::async_trait]
         impl ::polecen::command::CommandArguments for #parent_name {
             async fn read_arguments<'a, I>(
-                mut args: I,
+                args: I,
                 position: u8,
                 ctx: ::polecen::arguments::parse::ArgumentParseContext<'a>,
             ) -> Result<Self, ::polecen::command::CommandArgumentsReadError>
             where
-                I: Iterator<Item = &'a str> + Send
+                I: Iterator + Send,
+                I::Item: Into<::polecen::arguments::tokenize::ArgumentToken<'a>>,
             {
+                #[allow(unused_mut)]
+                let mut args = args.map(Into::<::polecen::arguments::tokenize::ArgumentToken<'a>>::into);
                 Ok(#reader)
             }
+
+            #help_fns
         }
     });
+    #[cfg(feature = "interactions")]
+    structs.push(interactions::generate_interaction_impl(
+        input,
+        path,
+        &parent_name,
+        &children_structs,
+    ));
 
     parent_name
 }
@@ -0,0 +1,489 @@
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::quote;
+use syn::LitStr;
+
+use crate::input::{ArgumentDefault, ArgumentInput, ArgumentSpan, VariadicInput};
+
+/// Generate the reader of a command (without sub-commands).
+/// Named arguments are extracted first, then positional arguments are assigned in order,
+/// and finally all arguments are parsed concurrently.
+///
+/// # Arguments
+///
+/// * `arguments` - Arguments of the command
+/// * `path` - Full name of the command, reported in errors
+/// * `ctx_ident` - Ident of the ctx variable in scope
+/// * `args_ident` - Ident of the args variable in scope
+pub(crate) fn generate_command_reader(
+    arguments: &[ArgumentInput],
+    path: &str,
+    ctx_ident: &Ident,
+    args_ident: &Ident,
+) -> TokenStream {
+    let err = quote!(::polecen::command::CommandArgumentsReadError);
+    let command = LitStr::new(path, Span::call_site());
+
+    let mut named_locals = Vec::new();
+    let mut named_arms = Vec::new();
+    // raw values of the fields, assigned before parsing
+    let mut raws = Vec::new();
+    let mut fields = Vec::new();
+    for (i, argument) in arguments.iter().enumerate() {
+        let ArgumentInput { name: field, named, .. } = argument;
+        let name = LitStr::new(&field.to_string(), field.span());
+        let raw = Ident::new(&format!("raw_{}", i), Span::call_site());
+        let parse = wrap_value(argument, parse_value(argument, path, ctx_ident));
+        let err_handler = missing_value(argument, path, quote!(end), ctx_ident);
+        let mut value = quote! {
+            if let Some((arg_position, arg_span, arg)) = #raw {
+                #parse
+            } else {
+                #err_handler
+            }
+        };
+
+        if let Some(named) = named {
+            let local = Ident::new(&format!("named_{}", i), Span::call_site());
+            let names = named.names();
+            if named.flag {
+                named_locals.push(quote! { let mut #local = false; });
+                named_arms.push(quote! {
+                    #(#names)|* if value.is_none() => {
+                        #local = true;
+                        continue;
+                    },
+                });
+                fields.push((field.clone(), FieldValue::Immediate(quote!(#local))));
+            } else {
+                let long = &named.long;
+                named_locals.push(quote! { let mut #local = None; });
+                named_arms.push(quote! {
+                    #(#names)|* | #long if value.is_some() || key != #long => {
+                        #local = Some(if let Some(value) = value {
+                            // point at the value only if the token was not quoted
+                            let value_span = match token.span {
+                                Some(span) if span.len() == token.value.len() => {
+                                    Some(span.end - value.len()..span.end)
+                                },
+                                span => span,
+                            };
+                            (token_position, value_span, value)
+                        } else if let Some(next) = #args_ident.next() {
+                            end = end.saturating_add(1);
+                            (token_position.saturating_add(1), next.span, next.value)
+                        } else {
+                            return Err(#err::RequiredArgumentMissing {
+                                position: end,
+                                command: String::from(#command),
+                                name: String::from(#name),
+                            });
+                        });
+                        continue;
+                    },
+                });
+                raws.push(quote! { let #raw = #local; });
+                fields.push((field.clone(), FieldValue::Parsed(value)));
+            }
+            continue;
+        }
+
+        if argument.variadic.is_some() {
+            raws.push(quote! {
+                let #raw = tokens
+                    .by_ref()
+                    .map(|(position, token)| (position, token.span, token.value))
+                    .collect::<Vec<_>>();
+            });
+            let parse = parse_values(argument, path, ctx_ident, quote!(#raw), quote!(end));
+            fields.push((
+                field.clone(),
+                FieldValue::Parsed(quote! {
+                    if #raw.is_empty() {
+                        #err_handler
+                    } else {
+                        #parse
+                    }
+                }),
+            ));
+            continue;
+        }
+
+        let take = match argument.span() {
+            ArgumentSpan::Fixed(1) => {
+                raws.push(quote! {
+                    let #raw = tokens.next().map(|(position, token)| (position, token.span, token.value));
+                });
+                fields.push((field.clone(), FieldValue::Parsed(value)));
+                continue;
+            },
+            ArgumentSpan::Fixed(n) => {
+                // reported when parsing, so errors of previous arguments come first
+                let partial = Ident::new(&format!("partial_{}", i), Span::call_site());
+                value = quote! {
+                    if #partial {
+                        return Err(#err::RequiredArgumentMissing {
+                            position: end,
+                            command: String::from(#command),
+                            name: String::from(#name),
+                        });
+                    }
+                    #value
+                };
+                quote! {
+                    let parts = tokens.by_ref().take(#n as usize).collect::<Vec<_>>();
+                    let #partial = !parts.is_empty() && parts.len() < #n as usize;
+                }
+            },
+            ArgumentSpan::Rest => quote! {
+                let parts = tokens.by_ref().collect::<Vec<_>>();
+            },
+        };
+        raws.push(quote! {
+            #take
+            let #raw = parts.first().map(|(position, first)| {
+                let span = match (&first.span, parts.last().and_then(|(_, last)| last.span.as_ref())) {
+                    (Some(first), Some(last)) => Some(first.start..last.end),
+                    _ => None,
+                };
+                // the text typed by the user, quotes and separators included,
+                // a single token is taken as is
+                let typed = first.source.filter(|_| parts.len() > 1).and_then(|source| {
+                    let mut typed = String::from(source.get(first.span.clone()?)?);
+                    for pair in parts.windows(2) {
+                        let ((previous_position, previous), (position, part)) = (&pair[0], &pair[1]);
+                        let span = part.span.clone()?;
+                        if *position == previous_position.saturating_add(1) {
+                            typed.push_str(source.get(previous.span.as_ref()?.end..span.end)?);
+                        } else {
+                            // named arguments in between are not part of the text
+                            typed.push(' ');
+                            typed.push_str(source.get(span)?);
+                        }
+                    }
+                    Some(typed)
+                });
+                let value = typed.unwrap_or_else(|| {
+                    parts.iter().map(|(_, part)| part.value).collect::<Vec<_>>().join(" ")
+                });
+                (*position, span, value)
+            });
+        });
+        fields.push((field.clone(), FieldValue::Parsed(value)));
+    }
+
+    // tokens with their position, without named arguments
+    let tokens = if named_arms.is_empty() {
+        quote! {
+            let tokens = #args_ident.collect::<Vec<_>>();
+            #[allow(unused_variables)]
+            let end = position.saturating_add(tokens.len().min(u8::MAX as usize) as u8);
+            let tokens = tokens
+                .into_iter()
+                .enumerate()
+                .map(|(i, token)| (position.saturating_add(i.min(u8::MAX as usize) as u8), token))
+                .collect::<Vec<_>>();
+        }
+    } else {
+        quote! {
+            #(#named_locals)*
+            let mut tokens = Vec::new();
+            let mut end = position;
+            let mut only_positional = false;
+            while let Some(token) = #args_ident.next() {
+                let token_position = end;
+                end = end.saturating_add(1);
+                if !only_positional {
+                    let (key, value) = match token.value.find('=') {
+                        Some(i) => (&token.value[..i], Some(&token.value[i + 1..])),
+                        None => (token.value, None),
+                    };
+                    match key {
+                        #(#named_arms)*
+                        "--" if value.is_none() => {
+                            only_positional = true;
+                            continue;
+                        },
+                        key if key.starts_with("--") => {
+                            return Err(#err::UnknownOption {
+                                position: token_position,
+                                span: token.span,
+                                command: String::from(#command),
+                                given: token.value.to_owned(),
+                            });
+                        },
+                        _ => {},
+                    }
+                }
+                tokens.push((token_position, token));
+            }
+        }
+    };
+
+    let construct = join_fields(fields, ctx_ident);
+    quote! {
+        {
+            #tokens
+            #[allow(unused_mut, unused_variables)]
+            let mut tokens = tokens.into_iter();
+            #(#raws)*
+            #construct
+        }
+    }
+}
+
+/// Value of a generated field.
+pub(crate) enum FieldValue {
+    /// Expression of a value known without parsing, e.g. a flag.
+    Immediate(TokenStream),
+    /// Body of an async block evaluating to the parsed value,
+    /// errors are returned with `return Err(..)` or `?`.
+    Parsed(TokenStream),
+}
+
+/// Construct `Self` from the values of its fields.
+///
+/// Parsed values are resolved concurrently, if some of them fail,
+/// the error with the lowest position is returned.
+pub(crate) fn join_fields(fields: Vec<(Ident, FieldValue)>, ctx_ident: &Ident) -> TokenStream {
+    let err = quote!(::polecen::command::CommandArgumentsReadError);
+
+    let mut futures = Vec::new();
+    let mut results = Vec::new();
+    let mut values = Vec::new();
+    let mut entries = Vec::new();
+    for (i, (field, value)) in fields.into_iter().enumerate() {
+        match value {
+            FieldValue::Immediate(value) => entries.push(quote! { #field: #value }),
+            FieldValue::Parsed(body) => {
+                let result = Ident::new(&format!("result_{}", i), Span::call_site());
+                let value = Ident::new(&format!("value_{}", i), Span::call_site());
+                futures.push(quote! {
+                    {
+                        let #ctx_ident = &#ctx_ident;
+                        async move { Ok::<_, #err>({ #body }) }
+                    }
+                });
+                entries.push(quote! { #field: #value });
+                results.push(result);
+                values.push(value);
+            },
+        }
+    }
+
+    if futures.is_empty() {
+        return quote! {
+            Self {
+                #(#entries),*
+            }
+        };
+    }
+    quote! {
+        match ::polecen::futures::join!(#(#futures),*) {
+            (#(Ok(#values),)*) => Self {
+                #(#entries),*
+            },
+            (#(#results,)*) => {
+                // not all results are `Ok`, so there is an error
+                let error = ::polecen::command::first_error(vec![#(#results.err()),*]);
+                return Err(error.expect("no argument error"));
+            },
+        }
+    }
+}
+
+/// Wrap a parsed value into the type of the generated field.
+pub(crate) fn wrap_value(argument: &ArgumentInput, parse: TokenStream) -> TokenStream {
+    if argument.optional {
+        quote! { Some(#parse) }
+    } else if argument.variadic.is_some() {
+        quote! { vec![#parse] }
+    } else {
+        parse
+    }
+}
+
+/// Value of the generated field when the argument is not given,
+/// `position` is the position to report if it was required.
+pub(crate) fn missing_value(
+    argument: &ArgumentInput,
+    path: &str,
+    position: TokenStream,
+    ctx_ident: &Ident,
+) -> TokenStream {
+    let name = LitStr::new(&argument.name.to_string(), argument.name.span());
+    let command = LitStr::new(path, Span::call_site());
+    let missing = quote! {
+        return Err(::polecen::command::CommandArgumentsReadError::RequiredArgumentMissing {
+            position: #position,
+            command: String::from(#command),
+            name: String::from(#name),
+        });
+    };
+
+    if let (true, Some(VariadicInput { min, .. })) = (argument.required, &argument.variadic) {
+        // lists report no value like too few values
+        let min = *min as usize;
+        quote! {
+            return Err(::polecen::command::CommandArgumentsReadError::NotEnoughValues {
+                position: #position,
+                command: String::from(#command),
+                name: String::from(#name),
+                min: #min,
+                given: 0,
+            });
+        }
+    } else if argument.required {
+        missing
+    } else if let Some(default) = &argument.default {
+        let context_field = match default {
+            ArgumentDefault::Value(expr) => return quote! { #expr },
+            ArgumentDefault::Author => quote!(author_id),
+            ArgumentDefault::Channel => quote!(channel_id),
+        };
+        // context defaults are parsed as if the id was given
+        let parse = wrap_value(argument, parse_value(argument, path, ctx_ident));
+        quote! {
+            if let Some(id) = #ctx_ident.#context_field {
+                let arg = id.to_string();
+                let arg_position = #position;
+                let arg_span = None;
+                #parse
+            } else {
+                #missing
+            }
+        }
+    } else if argument.optional {
+        quote! { None }
+    } else if argument.variadic.is_some() {
+        quote! { Vec::new() }
+    } else {
+        // flags
+        quote! { false }
+    }
+}
+
+/// Parse the values of a `Vec<T>` argument concurrently, after checking their number.
+///
+/// # Arguments
+///
+/// * `argument` - Variadic argument being parsed
+/// * `path` - Full name of the command, reported in errors
+/// * `ctx_ident` - Ident of the ctx variable in scope
+/// * `values` - Expression of the values, as a `Vec<(u8, Option<Range<usize>>, &str)>`
+///   of their position, span and raw value
+/// * `end` - Expression of the position to report if values are missing
+pub(crate) fn parse_values(
+    argument: &ArgumentInput,
+    path: &str,
+    ctx_ident: &Ident,
+    values: TokenStream,
+    end: TokenStream,
+) -> TokenStream {
+    let err = quote!(::polecen::command::CommandArgumentsReadError);
+    let name = LitStr::new(&argument.name.to_string(), argument.name.span());
+    let command = LitStr::new(path, Span::call_site());
+    let VariadicInput { min, max } = argument.variadic.as_ref().expect("argument is not variadic");
+
+    let min = *min as usize;
+    let min_check = (min > 0).then(|| {
+        quote! {
+            if values.len() < #min {
+                return Err(#err::NotEnoughValues {
+                    position: #end,
+                    command: String::from(#command),
+                    name: String::from(#name),
+                    min: #min,
+                    given: values.len(),
+                });
+            }
+        }
+    });
+    let max_check = max.map(|max| {
+        let max = max as usize;
+        quote! {
+            if values.len() > #max {
+                let (position, first, _) = &values[#max];
+                let span = match (first, &values[values.len() - 1].1) {
+                    (Some(first), Some(last)) => Some(first.start..last.end),
+                    _ => None,
+                };
+                return Err(#err::TooManyValues {
+                    position: *position,
+                    span,
+                    command: String::from(#command),
+                    name: String::from(#name),
+                    max: #max,
+                    given: values.len(),
+                });
+            }
+        }
+    });
+    let parse = parse_value(argument, path, ctx_ident);
+    quote! {
+        {
+            let values = #values;
+            #min_check
+            #max_check
+            let parsed = ::polecen::futures::future::join_all(values.into_iter().map(
+                |(arg_position, arg_span, arg)| async move {
+                    let value = #parse;
+                    Ok::<_, #err>(value)
+                },
+            ))
+            .await;
+            // in order, the first error has the lowest position
+            parsed.into_iter().collect::<Result<Vec<_>, #err>>()?
+        }
+    }
+}
+
+/// Parse `arg` with the argument type, mapping errors to `arg_position` and `arg_span`.
+fn parse_value(argument: &ArgumentInput, path: &str, ctx_ident: &Ident) -> TokenStream {
+    let ty = &argument.ty;
+    let parse = quote! {
+        <#ty as ::polecen::arguments::parse::ArgumentType>::parse_argument(
+            &#ctx_ident,
+            ::polecen::arguments::parse::ArgumentParseRaw {
+                value: arg.to_owned(),
+            },
+        )
+        .await
+    };
+    map_parse_error(argument, path, parse, quote!(arg_position), quote!(arg_span), quote! {
+        arg.to_owned()
+    })
+}
+
+/// Map the error of a parse result to a `ValueParseError` and propagate it.
+///
+/// # Arguments
+///
+/// * `argument` - Argument being parsed
+/// * `path` - Full name of the command, reported in errors
+/// * `parse` - Expression of the parse result
+/// * `position` - Expression of the position to report
+/// * `span` - Expression of the span to report
+/// * `value` - Expression of the raw value to report, as a `String`
+pub(crate) fn map_parse_error(
+    argument: &ArgumentInput,
+    path: &str,
+    parse: TokenStream,
+    position: TokenStream,
+    span: TokenStream,
+    value: TokenStream,
+) -> TokenStream {
+    let ty = &argument.ty;
+    let name = LitStr::new(&argument.name.to_string(), argument.name.span());
+    let command = LitStr::new(path, Span::call_site());
+    quote! {
+        #parse.map_err(|e| ::polecen::command::CommandArgumentsReadError::ValueParseError {
+            position: #position,
+            span: #span,
+            command: String::from(#command),
+            name: String::from(#name),
+            value: #value,
+            type_name: <#ty as ::polecen::arguments::parse::ArgumentType>::type_name(),
+            inner: e,
+        })?
+    }
+}
@@ -1,4 +1,5 @@
 use convert_case::{Case, Casing};
+use quote::ToTokens;
 use syn::Ident;
 
 pub(crate) trait ConvertCase {
@@ -10,3 +11,29 @@ impl ConvertCase for Ident {
         Ident::new(&self.to_string().to_case(case), self.span())
     }
 }
+
+/// Render tokens as they are written in the source, e.g. `Duration::from_secs(60)`.
+///
+/// Spans of several tokens can only be joined on nightly, the tokens are otherwise
+/// rendered by `TokenStream::to_string`, e.g. `Duration :: from_secs (60)`.
+pub(crate) fn tokens_to_string<T: ToTokens>(tokens: &T) -> String {
+    let tokens = tokens.to_token_stream();
+    let mut spans = tokens.clone().into_iter().map(|token| token.span());
+    let span = spans.next().and_then(|first| spans.try_fold(first, |span, next| span.join(next)));
+    span.and_then(|span| span.source_text()).unwrap_or_else(|| tokens.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn literals_and_operators_are_kept() {
+        let expr: syn::Expr = syn::parse_str(r#""a :: b""#).unwrap();
+        assert_eq!(tokens_to_string(&expr), r#""a :: b""#);
+        let expr: syn::Expr = syn::parse_str("x < y").unwrap();
+        assert_eq!(tokens_to_string(&expr), "x < y");
+        let expr: syn::Expr = syn::parse_str("f(a, b) > 0").unwrap();
+        assert_eq!(tokens_to_string(&expr), "f (a , b) > 0");
+    }
+}
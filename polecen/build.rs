@@ -6,5 +6,8 @@ fn main() {
         default_parsers_models: { feature = "default_parsers_models" },
         default_parsers_time: { feature = "default_parsers_time" },
         default_parsers: { any(default_parsers_primitives, default_parsers_models, default_parsers_time) },
+        interactions: { feature = "interactions" },
+        cache: { feature = "cache" },
+        testing: { feature = "testing" },
     }
 }
@@ -2,15 +2,19 @@
 //! Available with the feature `default_parsers`, this mod provides implementations
 //! of ArgumentType for many std types and serenity models.
 
+pub use list::*;
 #[cfg(default_parsers_models)]
+#[allow(unused_imports)]
 pub use models::*;
 #[cfg(default_parsers_primitives)]
+#[allow(unused_imports)]
 pub use primitives::*;
 #[cfg(default_parsers_time)]
+#[allow(unused_imports)]
 pub use time::*;
 
 macro_rules! default_impl {
-    ($ty:ty, $ctx:ident, $raw:ident, $inside:tt) => {
+    ($ty:ty, $ctx:ident, $raw:ident, $name:literal, $inside:tt $(, $kind:ident)?) => {
         #[async_trait]
         impl ArgumentType for $ty {
             async fn parse_argument<'a>(
@@ -19,20 +23,43 @@ macro_rules! default_impl {
             ) -> Result<Self, ArgumentParseError> {
                 $inside
             }
+
+            type_name!($name);
+            option_type!($($kind)?);
         }
     };
-    ($($ty:ty $(=> $into:ty)?),+) => {
-        $( default_impl!(> $ty, _ctx, raw $(=> $into)?); )+
+    ($($ty:ty $(=> $into:ty)?: $name:literal $([$kind:ident])?),+) => {
+        $( default_impl!(> $ty, _ctx, raw, $name $(=> $into)? $([$kind])?); )+
     };
-    (> $ty:ty, $ctx:ident, $raw:ident) => {
-        default_impl!($ty, $ctx, $raw, {
+    (> $ty:ty, $ctx:ident, $raw:ident, $name:literal $([$kind:ident])?) => {
+        default_impl!($ty, $ctx, $raw, $name, {
             Ok($raw.value.parse().map_err(|_| ArgumentParseError::InvalidValueFormat)?)
-        });
+        } $(, $kind)?);
     };
-    (> $ty:ty, $ctx:ident, $raw:ident => $into:ty) => {
-        default_impl!($ty, $ctx, $raw, {
+    (> $ty:ty, $ctx:ident, $raw:ident, $name:literal => $into:ty $([$kind:ident])?) => {
+        default_impl!($ty, $ctx, $raw, $name, {
             Ok($raw.value.parse::<$into>().map_err(|_| ArgumentParseError::InvalidValueFormat)?.into())
-        });
+        } $(, $kind)?);
+    };
+}
+
+/// Override the user-facing name of the type of an implementation.
+macro_rules! type_name {
+    ($name:literal) => {
+        fn type_name() -> String {
+            String::from($name)
+        }
+    };
+}
+
+/// Override the application command option type of an implementation.
+macro_rules! option_type {
+    () => {};
+    ($kind:ident) => {
+        #[cfg(interactions)]
+        fn option_type() -> crate::interactions::ApplicationCommandOptionType {
+            crate::interactions::ApplicationCommandOptionType::$kind
+        }
     };
 }
 
@@ -50,10 +77,27 @@ mod primitives {
         ) -> Result<Self, ArgumentParseError> {
             Ok(raw.value)
         }
+
+        type_name!("text");
     }
 
     default_impl!(
-        bool, char, f32, f64, i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize
+        bool: "boolean" [Boolean],
+        char: "character",
+        f32: "number" [Number],
+        f64: "number" [Number],
+        i8: "integer" [Integer],
+        i16: "integer" [Integer],
+        i32: "integer" [Integer],
+        i64: "integer" [Integer],
+        i128: "integer",
+        isize: "integer" [Integer],
+        u8: "integer" [Integer],
+        u16: "integer" [Integer],
+        u32: "integer" [Integer],
+        u64: "integer" [Integer],
+        u128: "integer",
+        usize: "integer" [Integer]
     );
 }
 
@@ -65,7 +109,10 @@ mod time {
 
     use crate::arguments::parse::*;
 
-    default_impl!(Duration => humantime::Duration, SystemTime => humantime::Timestamp);
+    default_impl!(
+        Duration => humantime::Duration: "duration",
+        SystemTime => humantime::Timestamp: "timestamp"
+    );
 }
 
 #[cfg(default_parsers_models)]
@@ -73,35 +120,130 @@ mod models {
     use async_trait::async_trait;
     use serenity::model::channel::{Channel, GuildChannel};
     use serenity::model::guild::{Member, Role};
-    use serenity::model::id::{ChannelId, RoleId, UserId};
+    use serenity::model::id::{ChannelId, GuildId, RoleId, UserId};
     use serenity::model::prelude::User;
 
     use crate::arguments::parse::*;
+    #[cfg(interactions)]
+    use crate::interactions::{
+        option_raw_value,
+        ApplicationCommandInteractionDataOption,
+        ApplicationCommandInteractionDataOptionValue as OptionValue,
+    };
 
     macro_rules! parse_id {
-        ($value:expr, $ty:ty) => {
-            $value.parse::<$ty>().map_err(|_| ArgumentParseError::InvalidValueFormat)?
+        ($value:expr, $ty:ty, $kind:ident) => {
+            <$ty>::from(parse_mention(&$value, MentionKind::$kind)?)
         };
     }
 
-    impl From<serenity::Error> for ArgumentParseError {
-        fn from(err: serenity::Error) -> Self {
-            Self::CannotParseInContext(format!("{}", err))
+    #[derive(Clone, Copy, PartialEq, Eq)]
+    enum MentionKind {
+        User,
+        Role,
+        Channel,
+    }
+
+    impl MentionKind {
+        fn name(self) -> &'static str {
+            match self {
+                Self::User => "user",
+                Self::Role => "role",
+                Self::Channel => "channel",
+            }
+        }
+    }
+
+    /// Parse an id given either as is or as a mention (`<@id>`, `<@!id>`, `<@&id>`, `<#id>`).
+    fn parse_mention(value: &str, expected: MentionKind) -> Result<u64, ArgumentParseError> {
+        let (kind, id) = if let Some(mention) =
+            value.strip_prefix('<').and_then(|value| value.strip_suffix('>'))
+        {
+            if let Some(id) = mention.strip_prefix("@&") {
+                (MentionKind::Role, id)
+            } else if let Some(id) =
+                mention.strip_prefix("@!").or_else(|| mention.strip_prefix('@'))
+            {
+                (MentionKind::User, id)
+            } else if let Some(id) = mention.strip_prefix('#') {
+                (MentionKind::Channel, id)
+            } else {
+                return Err(ArgumentParseError::InvalidValueFormat);
+            }
+        } else {
+            (expected, value)
+        };
+
+        let id = id.parse::<u64>().map_err(|_| ArgumentParseError::InvalidValueFormat)?;
+        if kind != expected {
+            return Err(ArgumentParseError::UnexpectedMention {
+                expected: expected.name(),
+                given: kind.name(),
+            });
+        }
+        Ok(id)
+    }
+
+    /// Parse an id or a mention, `None` if the value is neither (it may be a name).
+    fn parse_id_or_name(
+        value: &str,
+        expected: MentionKind,
+    ) -> Result<Option<u64>, ArgumentParseError> {
+        match parse_mention(value, expected) {
+            Ok(id) => Ok(Some(id)),
+            Err(ArgumentParseError::InvalidValueFormat) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn expect_guild(ctx: &ArgumentParseContext<'_>) -> Result<GuildId, ArgumentParseError> {
+        ctx.guild_id
+            .ok_or_else(|| ArgumentParseError::CannotParseInContext("Expected guild".to_owned()))
+    }
+
+    /// Only entity matching a name, candidates are listed with `display` if there are several.
+    fn single_match<T>(
+        mut matches: Vec<T>,
+        display: impl Fn(&T) -> String,
+    ) -> Result<T, ArgumentParseError> {
+        match matches.len() {
+            0 => Err(ArgumentParseError::NotFound),
+            1 => Ok(matches.remove(0)),
+            _ => Err(ArgumentParseError::Ambiguous(matches.iter().map(display).collect())),
         }
     }
 
+    /// Raw value of an option that couldn't be resolved by Discord.
+    #[cfg(interactions)]
+    fn unresolved_option(
+        option: &ApplicationCommandInteractionDataOption,
+    ) -> Result<ArgumentParseRaw, ArgumentParseError> {
+        let value = option_raw_value(option).ok_or(ArgumentParseError::InvalidValueType)?;
+        Ok(ArgumentParseRaw { value })
+    }
+
     #[async_trait]
     impl ArgumentType for User {
         async fn parse_argument<'a>(
             ctx: &ArgumentParseContext<'a>,
             raw: ArgumentParseRaw,
         ) -> Result<Self, ArgumentParseError> {
-            let user = parse_id!(raw.value, UserId)
-                .to_user(&ctx.event_ctx.http)
-                .await
-                .map_err(ArgumentParseError::from)?;
-            Ok(user)
+            ctx.resolver.user(parse_id!(raw.value, UserId, User), ctx.strategy).await
+        }
+
+        #[cfg(interactions)]
+        async fn parse_option<'a>(
+            ctx: &ArgumentParseContext<'a>,
+            option: &ApplicationCommandInteractionDataOption,
+        ) -> Result<Self, ArgumentParseError> {
+            match &option.resolved {
+                Some(OptionValue::User(user, _)) => Ok(user.clone()),
+                _ => Self::parse_argument(ctx, unresolved_option(option)?).await,
+            }
         }
+
+        type_name!("user");
+        option_type!(User);
     }
 
     #[async_trait]
@@ -110,16 +252,52 @@ mod models {
             ctx: &ArgumentParseContext<'a>,
             raw: ArgumentParseRaw,
         ) -> Result<Self, ArgumentParseError> {
-            if let Some(guild_id) = ctx.guild_id {
-                let member = guild_id
-                    .member(&ctx.event_ctx.http, parse_id!(raw.value, UserId))
-                    .await
-                    .map_err(ArgumentParseError::from)?;
-                Ok(member)
-            } else {
-                Err(ArgumentParseError::CannotParseInContext("Expected guild".to_owned()))
+            let guild_id = expect_guild(ctx)?;
+            if let Some(user_id) = parse_id_or_name(&raw.value, MentionKind::User)? {
+                return ctx.resolver.member(guild_id, UserId(user_id), ctx.strategy).await;
+            }
+
+            // name, nickname or name#discriminator
+            let name = raw.value.to_lowercase();
+            let matches = ctx
+                .resolver
+                .find_members(
+                    guild_id,
+                    &|member| {
+                        member.user.name.to_lowercase() == name
+                            || member.user.tag().to_lowercase() == name
+                            || member.nick.as_ref().is_some_and(|nick| nick.to_lowercase() == name)
+                    },
+                    ctx.strategy,
+                )
+                .await?;
+            single_match(matches, |member| member.user.tag())
+        }
+
+        #[cfg(interactions)]
+        async fn parse_option<'a>(
+            ctx: &ArgumentParseContext<'a>,
+            option: &ApplicationCommandInteractionDataOption,
+        ) -> Result<Self, ArgumentParseError> {
+            match (&option.resolved, ctx.guild_id) {
+                (Some(OptionValue::User(user, Some(member))), Some(guild_id)) => {
+                    // interactions only provide a partial member, complete it with known data
+                    let complete = || {
+                        let mut member = serde_json::to_value(member)?;
+                        member["user"] = serde_json::to_value(user)?;
+                        member["guild_id"] = serde_json::to_value(guild_id)?;
+                        serde_json::from_value(member)
+                    };
+                    complete().map_err(|e: serde_json::Error| {
+                        ArgumentParseError::CannotParseInContext(format!("{}", e))
+                    })
+                },
+                _ => Self::parse_argument(ctx, unresolved_option(option)?).await,
             }
         }
+
+        type_name!("member");
+        option_type!(User);
     }
 
     #[async_trait]
@@ -128,12 +306,23 @@ mod models {
             ctx: &ArgumentParseContext<'a>,
             raw: ArgumentParseRaw,
         ) -> Result<Self, ArgumentParseError> {
-            let channel = parse_id!(raw.value, ChannelId)
-                .to_channel(&ctx.event_ctx.http)
-                .await
-                .map_err(ArgumentParseError::from)?;
-            Ok(channel)
+            ctx.resolver.channel(parse_id!(raw.value, ChannelId, Channel), ctx.strategy).await
+        }
+
+        #[cfg(interactions)]
+        async fn parse_option<'a>(
+            ctx: &ArgumentParseContext<'a>,
+            option: &ApplicationCommandInteractionDataOption,
+        ) -> Result<Self, ArgumentParseError> {
+            // interactions only provide a partial channel, resolve the complete one
+            if let Some(OptionValue::Channel(channel)) = &option.resolved {
+                return ctx.resolver.channel(channel.id, ctx.strategy).await;
+            }
+            Self::parse_argument(ctx, unresolved_option(option)?).await
         }
+
+        type_name!("channel");
+        option_type!(Channel);
     }
 
     #[async_trait]
@@ -142,14 +331,43 @@ mod models {
             ctx: &ArgumentParseContext<'a>,
             raw: ArgumentParseRaw,
         ) -> Result<Self, ArgumentParseError> {
-            let channel = Channel::parse_argument(ctx, raw).await?.guild().ok_or(
+            if parse_id_or_name(&raw.value, MentionKind::Channel)?.is_some() {
+                let channel = Channel::parse_argument(ctx, raw).await?.guild().ok_or(
+                    ArgumentParseError::CannotParseInContext(
+                        "Channel does not belong to a guild".to_owned(),
+                    ),
+                )?;
+                return Ok(channel);
+            }
+
+            let guild_id = expect_guild(ctx)?;
+            let name = raw.value.strip_prefix('#').unwrap_or(&raw.value).to_lowercase();
+            let matches = ctx
+                .resolver
+                .find_channels(
+                    guild_id,
+                    &|channel| channel.name.to_lowercase() == name,
+                    ctx.strategy,
+                )
+                .await?;
+            single_match(matches, |channel| format!("#{} ({})", channel.name, channel.id))
+        }
+
+        #[cfg(interactions)]
+        async fn parse_option<'a>(
+            ctx: &ArgumentParseContext<'a>,
+            option: &ApplicationCommandInteractionDataOption,
+        ) -> Result<Self, ArgumentParseError> {
+            let channel = Channel::parse_option(ctx, option).await?.guild().ok_or(
                 ArgumentParseError::CannotParseInContext(
                     "Channel does not belong to a guild".to_owned(),
-                )
-                .to_owned(),
+                ),
             )?;
             Ok(channel)
         }
+
+        type_name!("channel");
+        option_type!(Channel);
     }
 
     #[async_trait]
@@ -158,26 +376,160 @@ mod models {
             ctx: &ArgumentParseContext<'a>,
             raw: ArgumentParseRaw,
         ) -> Result<Self, ArgumentParseError> {
-            if let Some(guild_id) = ctx.guild_id {
-                let role_id = parse_id!(raw.value, RoleId);
-                if let Some(role) = ctx
-                    .event_ctx
-                    .cache
-                    .guild_field(&guild_id, |guild| guild.roles.get(&role_id).map(|r| r.clone()))
-                    .await
-                    .ok_or(ArgumentParseError::CannotParseInContext(
-                        "Guild not in cache".to_owned(),
-                    ))?
-                {
-                    Ok(role)
-                } else {
-                    Err(ArgumentParseError::CannotParseInContext(
-                        "Role does not exist in guild".to_owned(),
-                    ))
-                }
-            } else {
-                Err(ArgumentParseError::CannotParseInContext("Expected guild".to_owned()))
+            let guild_id = expect_guild(ctx)?;
+            if let Some(role_id) = parse_id_or_name(&raw.value, MentionKind::Role)? {
+                return ctx.resolver.role(guild_id, RoleId(role_id), ctx.strategy).await;
+            }
+
+            let name = raw.value.to_lowercase();
+            let matches = ctx
+                .resolver
+                .find_roles(guild_id, &|role| role.name.to_lowercase() == name, ctx.strategy)
+                .await?;
+            single_match(matches, |role| format!("@{} ({})", role.name, role.id))
+        }
+
+        #[cfg(interactions)]
+        async fn parse_option<'a>(
+            ctx: &ArgumentParseContext<'a>,
+            option: &ApplicationCommandInteractionDataOption,
+        ) -> Result<Self, ArgumentParseError> {
+            match &option.resolved {
+                Some(OptionValue::Role(role)) => Ok(role.clone()),
+                _ => Self::parse_argument(ctx, unresolved_option(option)?).await,
+            }
+        }
+
+        type_name!("role");
+        option_type!(Role);
+    }
+}
+
+mod list {
+    use std::fmt;
+    use std::marker::PhantomData;
+    use std::ops::Deref;
+
+    use async_trait::async_trait;
+
+    use crate::arguments::parse::*;
+
+    /// Characters separating the elements of a [`List`].
+    pub trait Separator: Send + Sync {
+        const CHARS: &'static [char];
+    }
+
+    /// Elements separated with `,`.
+    pub struct Comma;
+
+    /// Elements separated with `|`.
+    pub struct Pipe;
+
+    /// Elements separated with either `,` or `|`.
+    pub struct CommaOrPipe;
+
+    impl Separator for Comma {
+        const CHARS: &'static [char] = &[','];
+    }
+
+    impl Separator for Pipe {
+        const CHARS: &'static [char] = &['|'];
+    }
+
+    impl Separator for CommaOrPipe {
+        const CHARS: &'static [char] = &[',', '|'];
+    }
+
+    /// List of values given as a single token, e.g. `yes,no,maybe` or `@a|@b`.
+    ///
+    /// Elements are trimmed and parsed with the argument type of `T`, empty elements
+    /// are ignored. When an element can't be parsed, the error is an
+    /// [`ArgumentParseError::InvalidElement`] telling which one.
+    pub struct List<T, S = CommaOrPipe> {
+        pub items: Vec<T>,
+        separator: PhantomData<fn() -> S>,
+    }
+
+    impl<T, S> List<T, S> {
+        pub fn into_inner(self) -> Vec<T> {
+            self.items
+        }
+    }
+
+    impl<T, S> From<Vec<T>> for List<T, S> {
+        fn from(items: Vec<T>) -> Self {
+            Self { items, separator: PhantomData }
+        }
+    }
+
+    impl<T, S> Deref for List<T, S> {
+        type Target = Vec<T>;
+
+        fn deref(&self) -> &Self::Target {
+            &self.items
+        }
+    }
+
+    impl<T, S> IntoIterator for List<T, S> {
+        type IntoIter = std::vec::IntoIter<T>;
+        type Item = T;
+
+        fn into_iter(self) -> Self::IntoIter {
+            self.items.into_iter()
+        }
+    }
+
+    impl<T: Clone, S> Clone for List<T, S> {
+        fn clone(&self) -> Self {
+            Self::from(self.items.clone())
+        }
+    }
+
+    impl<T: fmt::Debug, S> fmt::Debug for List<T, S> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_list().entries(&self.items).finish()
+        }
+    }
+
+    #[async_trait]
+    impl<T, S> ArgumentType for List<T, S>
+    where
+        T: ArgumentType + Send,
+        S: Separator,
+    {
+        async fn parse_argument<'a>(
+            ctx: &ArgumentParseContext<'a>,
+            raw: ArgumentParseRaw,
+        ) -> Result<Self, ArgumentParseError> {
+            // indexes count empty elements, as the user sees them
+            let elements = raw
+                .value
+                .split(S::CHARS)
+                .map(str::trim)
+                .enumerate()
+                .filter(|(_, element)| !element.is_empty())
+                .collect::<Vec<_>>();
+            if elements.is_empty() {
+                return Err(ArgumentParseError::InvalidValueFormat);
             }
+
+            let parsed = futures::future::join_all(elements.iter().map(|(_, element)| {
+                T::parse_argument(ctx, ArgumentParseRaw { value: (*element).to_owned() })
+            }))
+            .await;
+            let mut items = Vec::with_capacity(parsed.len());
+            for (result, (index, element)) in parsed.into_iter().zip(elements) {
+                items.push(result.map_err(|e| ArgumentParseError::InvalidElement {
+                    index,
+                    value: element.to_owned(),
+                    inner: Box::new(e),
+                })?);
+            }
+            Ok(Self::from(items))
+        }
+
+        fn type_name() -> String {
+            format!("{} list", T::type_name())
         }
     }
 }
@@ -2,3 +2,5 @@
 pub mod default;
 pub mod parse;
 pub mod prelude;
+pub mod resolve;
+pub mod tokenize;
@@ -2,18 +2,90 @@ use std::fmt;
 
 use async_trait::async_trait;
 use serenity::client::Context;
-use serenity::model::id::GuildId;
+use serenity::model::channel::Message;
+use serenity::model::id::{ChannelId, GuildId, UserId};
+
+use crate::arguments::resolve::{EntityResolver, ResolveStrategy};
+#[cfg(interactions)]
+use crate::interactions::{
+    ApplicationCommandInteraction,
+    ApplicationCommandInteractionDataOption,
+    ApplicationCommandOptionType,
+};
 
 #[derive(Clone)]
 #[non_exhaustive]
 pub struct ArgumentParseContext<'a> {
-    pub event_ctx: &'a Context,
+    /// Event context, `None` if the arguments are read with a custom resolver.
+    pub event_ctx: Option<&'a Context>,
+    /// Source of the entities parsers resolve ids and names to.
+    pub resolver: &'a dyn EntityResolver,
+    /// Whether entities are resolved from the cache, with HTTP or both.
+    pub strategy: ResolveStrategy,
     pub guild_id: Option<GuildId>,
+    /// User who invoked the command, if known.
+    pub author_id: Option<UserId>,
+    /// Channel the command was invoked in, if known.
+    pub channel_id: Option<ChannelId>,
+    /// Message the command was invoked with, if invoked with a message.
+    pub message: Option<&'a Message>,
 }
 
 impl<'a> ArgumentParseContext<'a> {
     pub fn new(event_ctx: &'a Context, guild_id: Option<GuildId>) -> ArgumentParseContext<'a> {
-        Self { event_ctx, guild_id }
+        Self { event_ctx: Some(event_ctx), ..Self::with_resolver(event_ctx, guild_id) }
+    }
+
+    /// Context resolving entities with `resolver` instead of an event context.
+    pub fn with_resolver(
+        resolver: &'a dyn EntityResolver,
+        guild_id: Option<GuildId>,
+    ) -> ArgumentParseContext<'a> {
+        Self {
+            event_ctx: None,
+            resolver,
+            strategy: ResolveStrategy::default(),
+            guild_id,
+            author_id: None,
+            channel_id: None,
+            message: None,
+        }
+    }
+
+    /// Context of a command invoked with a message.
+    pub fn from_message(event_ctx: &'a Context, message: &'a Message) -> ArgumentParseContext<'a> {
+        Self {
+            event_ctx: Some(event_ctx),
+            resolver: event_ctx,
+            strategy: ResolveStrategy::default(),
+            guild_id: message.guild_id,
+            author_id: Some(message.author.id),
+            channel_id: Some(message.channel_id),
+            message: Some(message),
+        }
+    }
+
+    /// Context of a command invoked with an application command interaction.
+    #[cfg(interactions)]
+    pub fn from_interaction(
+        event_ctx: &'a Context,
+        interaction: &'a ApplicationCommandInteraction,
+    ) -> ArgumentParseContext<'a> {
+        Self {
+            event_ctx: Some(event_ctx),
+            resolver: event_ctx,
+            strategy: ResolveStrategy::default(),
+            guild_id: interaction.guild_id,
+            author_id: Some(interaction.user.id),
+            channel_id: Some(interaction.channel_id),
+            message: None,
+        }
+    }
+
+    /// Use another resolution strategy, e.g. `ResolveStrategy::CacheOnly` to never use HTTP.
+    pub fn with_strategy(mut self, strategy: ResolveStrategy) -> ArgumentParseContext<'a> {
+        self.strategy = strategy;
+        self
     }
 }
 
@@ -21,10 +93,32 @@ impl<'a> ArgumentParseContext<'a> {
 pub enum ArgumentParseError {
     InvalidValueType,
     InvalidValueFormat,
+    /// A mention of another kind of entity was given, e.g. a role mention for a user.
+    UnexpectedMention {
+        expected: &'static str,
+        given: &'static str,
+    },
+    /// No entity matches the value.
+    NotFound,
+    /// Several entities match the value, contains a display of each candidate.
+    Ambiguous(Vec<String>),
     CannotParseInContext(String),
+    /// An element of a list can't be parsed, `index` starts at 0.
+    InvalidElement {
+        index: usize,
+        value: String,
+        inner: Box<ArgumentParseError>,
+    },
 }
 
-impl std::error::Error for ArgumentParseError {}
+impl std::error::Error for ArgumentParseError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::InvalidElement { inner, .. } => Some(inner.as_ref()),
+            _ => None,
+        }
+    }
+}
 
 impl fmt::Display for ArgumentParseError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -35,8 +129,21 @@ impl fmt::Display for ArgumentParseError {
             Self::InvalidValueFormat => {
                 write!(f, "The value doesn't match the expected format.")
             },
-            Self::CannotParseInContext { .. } => {
-                write!(f, "The value cannot be parsed in the current context.")
+            Self::UnexpectedMention { expected, given } => {
+                write!(f, "Expected a {} mention, got a {} mention.", expected, given)
+            },
+            Self::NotFound => {
+                write!(f, "No matching entity was found.")
+            },
+            Self::Ambiguous(candidates) => {
+                write!(f, "The value matches several entities: {}.", candidates.join(", "))
+            },
+            Self::CannotParseInContext(reason) => {
+                let reason = reason.trim_end_matches('.');
+                write!(f, "The value cannot be parsed in the current context: {}.", reason)
+            },
+            Self::InvalidElement { index, value, inner } => {
+                write!(f, "Element {} (`{}`) is invalid. {}", index + 1, value, inner)
             },
         }
     }
@@ -56,4 +163,32 @@ where
         ctx: &ArgumentParseContext<'a>,
         raw: ArgumentParseRaw,
     ) -> Result<Self, ArgumentParseError>;
+
+    /// User-facing name of the type, used in error messages.
+    ///
+    /// Defaults to the name of the Rust type, in lowercase.
+    fn type_name() -> String {
+        let name = std::any::type_name::<Self>();
+        let name = name.split('<').next().unwrap_or(name);
+        name.rsplit("::").next().unwrap_or(name).to_lowercase()
+    }
+
+    /// Parse an argument from an application command option.
+    ///
+    /// By default, the value of the option is parsed as if it was given in a message.
+    #[cfg(interactions)]
+    async fn parse_option<'a>(
+        ctx: &ArgumentParseContext<'a>,
+        option: &ApplicationCommandInteractionDataOption,
+    ) -> Result<Self, ArgumentParseError> {
+        let value = crate::interactions::option_raw_value(option)
+            .ok_or(ArgumentParseError::InvalidValueType)?;
+        Self::parse_argument(ctx, ArgumentParseRaw { value }).await
+    }
+
+    /// Type of the application command option this argument is exposed as.
+    #[cfg(interactions)]
+    fn option_type() -> ApplicationCommandOptionType {
+        ApplicationCommandOptionType::String
+    }
 }
@@ -1,4 +1,5 @@
 #[cfg(default_parsers)]
 pub use super::default;
 pub use super::parse::*;
+pub use super::resolve::ResolveStrategy;
 pub use crate::command::{CommandArguments, CommandArgumentsReadError};
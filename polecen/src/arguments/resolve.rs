@@ -0,0 +1,288 @@
+//! # Entity resolution
+//! Parsers of Discord models don't fetch entities directly, they go through the
+//! [`EntityResolver`] of their [`ArgumentParseContext`](super::parse::ArgumentParseContext).
+//!
+//! serenity's `Context` is the resolver used by default. Another resolver can be given
+//! with `ArgumentParseContext::with_resolver`, e.g. the in-memory one from `polecen::testing`.
+//!
+//! The [`ResolveStrategy`] of the context decides whether entities come from the cache,
+//! from HTTP requests or both. Without the `cache` feature, serenity's cache is not available
+//! and entities are always fetched with HTTP.
+
+use std::future::Future;
+
+use async_trait::async_trait;
+use serenity::client::Context;
+use serenity::model::channel::{Channel, GuildChannel};
+use serenity::model::guild::{Guild, Member, Role};
+use serenity::model::id::{ChannelId, GuildId, RoleId, UserId};
+use serenity::model::user::User;
+
+use crate::arguments::parse::ArgumentParseError;
+
+/// Predicate used to search entities of a guild.
+pub type Filter<'f, T> = &'f (dyn Fn(&T) -> bool + Send + Sync);
+
+/// Where entities are looked up.
+///
+/// Full guilds are only available from the cache, so [`EntityResolver::guild`]
+/// fails with `HttpOnly` on serenity's `Context`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ResolveStrategy {
+    /// Only use the cache, never make HTTP requests.
+    CacheOnly,
+    /// Use the cache, make HTTP requests for entities that are not cached.
+    #[default]
+    CacheThenHttp,
+    /// Always make HTTP requests, e.g. to get up-to-date entities.
+    HttpOnly,
+}
+
+/// Source of the Discord entities parsers resolve ids and names to.
+///
+/// Resolvers without a cache may ignore the strategy.
+#[async_trait]
+pub trait EntityResolver: Send + Sync {
+    async fn user(
+        &self,
+        user_id: UserId,
+        strategy: ResolveStrategy,
+    ) -> Result<User, ArgumentParseError>;
+
+    async fn member(
+        &self,
+        guild_id: GuildId,
+        user_id: UserId,
+        strategy: ResolveStrategy,
+    ) -> Result<Member, ArgumentParseError>;
+
+    async fn channel(
+        &self,
+        channel_id: ChannelId,
+        strategy: ResolveStrategy,
+    ) -> Result<Channel, ArgumentParseError>;
+
+    async fn role(
+        &self,
+        guild_id: GuildId,
+        role_id: RoleId,
+        strategy: ResolveStrategy,
+    ) -> Result<Role, ArgumentParseError>;
+
+    async fn guild(
+        &self,
+        guild_id: GuildId,
+        strategy: ResolveStrategy,
+    ) -> Result<Guild, ArgumentParseError>;
+
+    /// Members of a guild matching `filter`, used to look members up by name.
+    ///
+    /// Defaults to filtering the members of [`guild`](Self::guild).
+    async fn find_members(
+        &self,
+        guild_id: GuildId,
+        filter: Filter<'_, Member>,
+        strategy: ResolveStrategy,
+    ) -> Result<Vec<Member>, ArgumentParseError> {
+        let guild = self.guild(guild_id, strategy).await?;
+        Ok(guild.members.into_values().filter(|m| filter(m)).collect())
+    }
+
+    /// Channels of a guild matching `filter`, used to look channels up by name.
+    ///
+    /// Defaults to filtering the channels of [`guild`](Self::guild).
+    async fn find_channels(
+        &self,
+        guild_id: GuildId,
+        filter: Filter<'_, GuildChannel>,
+        strategy: ResolveStrategy,
+    ) -> Result<Vec<GuildChannel>, ArgumentParseError> {
+        let guild = self.guild(guild_id, strategy).await?;
+        Ok(guild.channels.into_values().filter(|c| filter(c)).collect())
+    }
+
+    /// Roles of a guild matching `filter`, used to look roles up by name.
+    ///
+    /// Defaults to filtering the roles of [`guild`](Self::guild).
+    async fn find_roles(
+        &self,
+        guild_id: GuildId,
+        filter: Filter<'_, Role>,
+        strategy: ResolveStrategy,
+    ) -> Result<Vec<Role>, ArgumentParseError> {
+        let guild = self.guild(guild_id, strategy).await?;
+        Ok(guild.roles.into_values().filter(|r| filter(r)).collect())
+    }
+}
+
+impl From<serenity::Error> for ArgumentParseError {
+    fn from(err: serenity::Error) -> Self {
+        Self::CannotParseInContext(format!("{}", err))
+    }
+}
+
+/// Lookup in the cache, always a cache miss without the `cache` feature.
+macro_rules! cached {
+    ($lookup:expr) => {{
+        #[cfg(cache)]
+        let cached = $lookup;
+        #[cfg(not(cache))]
+        let cached = futures::future::ready(None);
+        cached
+    }};
+}
+
+fn not_cached(entity: &str) -> ArgumentParseError {
+    ArgumentParseError::CannotParseInContext(format!("{} not in cache", entity))
+}
+
+/// Resolve an entity following `strategy`.
+///
+/// # Arguments
+///
+/// * `strategy` - Resolution strategy
+/// * `entity` - Name of the entity, reported when it is not cached
+/// * `cached` - Lookup in the cache, `None` on cache miss
+/// * `fetch` - HTTP request
+pub(crate) async fn resolve<T>(
+    strategy: ResolveStrategy,
+    entity: &str,
+    cached: impl Future<Output = Option<T>>,
+    fetch: impl Future<Output = serenity::Result<T>>,
+) -> Result<T, ArgumentParseError> {
+    if strategy != ResolveStrategy::HttpOnly {
+        if let Some(value) = cached.await {
+            return Ok(value);
+        }
+        if strategy == ResolveStrategy::CacheOnly {
+            return Err(not_cached(entity));
+        }
+    }
+    Ok(fetch.await?)
+}
+
+/// Whether all the members of a guild are known, they may not be in the cache
+/// of large guilds or without the members intent.
+#[cfg(any(cache, testing))]
+pub(crate) fn has_all_members(guild: &Guild) -> bool {
+    guild.members.len() as u64 >= guild.member_count
+}
+
+#[async_trait]
+impl EntityResolver for Context {
+    async fn user(
+        &self,
+        user_id: UserId,
+        strategy: ResolveStrategy,
+    ) -> Result<User, ArgumentParseError> {
+        resolve(strategy, "User", cached!(self.cache.user(user_id)), self.http.get_user(user_id.0))
+            .await
+    }
+
+    async fn member(
+        &self,
+        guild_id: GuildId,
+        user_id: UserId,
+        strategy: ResolveStrategy,
+    ) -> Result<Member, ArgumentParseError> {
+        let cached = cached!(self.cache.member(guild_id, user_id));
+        let fetch = self.http.get_member(guild_id.0, user_id.0);
+        resolve(strategy, "Member", cached, fetch).await
+    }
+
+    async fn channel(
+        &self,
+        channel_id: ChannelId,
+        strategy: ResolveStrategy,
+    ) -> Result<Channel, ArgumentParseError> {
+        let cached = cached!(self.cache.channel(channel_id));
+        let fetch = self.http.get_channel(channel_id.0);
+        resolve(strategy, "Channel", cached, fetch).await
+    }
+
+    async fn role(
+        &self,
+        guild_id: GuildId,
+        role_id: RoleId,
+        strategy: ResolveStrategy,
+    ) -> Result<Role, ArgumentParseError> {
+        // roles of a cached guild are complete, a missing role doesn't exist
+        let cached =
+            cached!(self.cache.guild_field(guild_id, |guild| guild.roles.get(&role_id).cloned()));
+        let fetch = async {
+            let roles = self.http.get_guild_roles(guild_id.0).await?;
+            Ok(roles.into_iter().find(|role| role.id == role_id))
+        };
+        resolve(strategy, "Guild", cached, fetch).await?.ok_or(ArgumentParseError::NotFound)
+    }
+
+    #[cfg_attr(not(cache), allow(unused_variables))]
+    async fn guild(
+        &self,
+        guild_id: GuildId,
+        strategy: ResolveStrategy,
+    ) -> Result<Guild, ArgumentParseError> {
+        match strategy {
+            ResolveStrategy::HttpOnly => Err(ArgumentParseError::CannotParseInContext(
+                "Guild can't be fetched with HTTP".to_owned(),
+            )),
+            _ => cached!(self.cache.guild(guild_id)).await.ok_or_else(|| not_cached("Guild")),
+        }
+    }
+
+    /// At most 1000 members are fetched with HTTP.
+    async fn find_members(
+        &self,
+        guild_id: GuildId,
+        filter: Filter<'_, Member>,
+        strategy: ResolveStrategy,
+    ) -> Result<Vec<Member>, ArgumentParseError> {
+        // nothing matching among partial members may still match a member who isn't cached
+        let cached = cached!(async {
+            let found = self.cache.guild_field(guild_id, |guild| {
+                let found =
+                    guild.members.values().filter(|m| filter(m)).cloned().collect::<Vec<_>>();
+                Some(found).filter(|found| !found.is_empty() || has_all_members(guild))
+            });
+            found.await.flatten()
+        });
+        let fetch = async {
+            let members = self.http.get_guild_members(guild_id.0, Some(1000), None).await?;
+            Ok(members.into_iter().filter(|m| filter(m)).collect())
+        };
+        resolve(strategy, "Member", cached, fetch).await
+    }
+
+    async fn find_channels(
+        &self,
+        guild_id: GuildId,
+        filter: Filter<'_, GuildChannel>,
+        strategy: ResolveStrategy,
+    ) -> Result<Vec<GuildChannel>, ArgumentParseError> {
+        // channels of a cached guild are complete, like its roles
+        let cached = cached!(self.cache.guild_field(guild_id, |guild| {
+            guild.channels.values().filter(|c| filter(c)).cloned().collect()
+        }));
+        let fetch = async {
+            let channels = self.http.get_channels(guild_id.0).await?;
+            Ok(channels.into_iter().filter(|c| filter(c)).collect())
+        };
+        resolve(strategy, "Guild", cached, fetch).await
+    }
+
+    async fn find_roles(
+        &self,
+        guild_id: GuildId,
+        filter: Filter<'_, Role>,
+        strategy: ResolveStrategy,
+    ) -> Result<Vec<Role>, ArgumentParseError> {
+        let cached = cached!(self.cache.guild_field(guild_id, |guild| {
+            guild.roles.values().filter(|r| filter(r)).cloned().collect()
+        }));
+        let fetch = async {
+            let roles = self.http.get_guild_roles(guild_id.0).await?;
+            Ok(roles.into_iter().filter(|r| filter(r)).collect())
+        };
+        resolve(strategy, "Guild", cached, fetch).await
+    }
+}
@@ -0,0 +1,244 @@
+//! # Message content tokenizer
+//! Splits raw message content into argument tokens, the way users expect
+//! to type them in chat.
+//!
+//! * Tokens are separated by whitespace.
+//! * A token starting with a quote (`"`, `'`, `“`, `‘`) extends to the matching
+//!   closing quote, the quotes themselves are not part of the token.
+//!   An unterminated quote is kept as a regular character.
+//! * A backslash escapes a quote, a whitespace or another backslash.
+//!   Other backslashes are kept as is.
+//! * A fenced code block (` ``` `) is kept verbatim as a single token, fences included.
+//!
+//! Use [`tokenize_spanned`] to keep the location of each token in the content,
+//! which lets errors point at the offending token.
+
+use std::iter::Peekable;
+use std::ops::Range;
+use std::str::CharIndices;
+
+const FENCE: &str = "```";
+
+/// Split message content into tokens.
+pub fn tokenize(content: &str) -> Vec<String> {
+    Tokenizer::new(content).collect()
+}
+
+/// Split message content into tokens, keeping their location in the content.
+pub fn tokenize_spanned(content: &str) -> Vec<Token> {
+    let mut tokenizer = Tokenizer::new(content);
+    std::iter::from_fn(|| tokenizer.next_token()).collect()
+}
+
+/// Token of a message content.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Token {
+    pub value: String,
+    /// Byte range of the token in the content, quotes included.
+    pub span: Range<usize>,
+}
+
+/// Token read by `CommandArguments::read_arguments`.
+///
+/// Can be built from a `&str`, in which case its location is unknown,
+/// or from a [`Token`], along with its content with [`with_source`](Self::with_source).
+#[derive(Clone, Debug)]
+pub struct ArgumentToken<'a> {
+    pub value: &'a str,
+    /// Byte range of the token in the content, if known.
+    pub span: Option<Range<usize>>,
+    /// Content the span refers to, if known.
+    ///
+    /// Arguments spanning several tokens are read from it, so they keep
+    /// the text the user typed between the tokens.
+    pub source: Option<&'a str>,
+}
+
+impl<'a> ArgumentToken<'a> {
+    /// Token of `content`, as returned by [`tokenize_spanned`].
+    pub fn with_source(token: &'a Token, content: &'a str) -> ArgumentToken<'a> {
+        Self { source: Some(content), ..Self::from(token) }
+    }
+}
+
+impl<'a> From<&'a str> for ArgumentToken<'a> {
+    fn from(value: &'a str) -> Self {
+        Self { value, span: None, source: None }
+    }
+}
+
+impl<'a> From<&'a String> for ArgumentToken<'a> {
+    fn from(value: &'a String) -> Self {
+        Self { value, span: None, source: None }
+    }
+}
+
+impl<'a> From<&'a Token> for ArgumentToken<'a> {
+    fn from(token: &'a Token) -> Self {
+        Self { value: &token.value, span: Some(token.span.clone()), source: None }
+    }
+}
+
+/// Iterator over the tokens of a message content.
+#[derive(Clone, Debug)]
+pub struct Tokenizer<'a> {
+    content: &'a str,
+    chars: Peekable<CharIndices<'a>>,
+}
+
+impl<'a> Tokenizer<'a> {
+    pub fn new(content: &'a str) -> Tokenizer<'a> {
+        Self { content, chars: content.char_indices().peekable() }
+    }
+
+    /// Read the next token along with its location.
+    pub fn next_token(&mut self) -> Option<Token> {
+        self.skip_whitespace();
+        let &(start, c) = self.chars.peek()?;
+
+        let mut value = None;
+        if self.content[start..].starts_with(FENCE) {
+            value = self.read_fenced(start);
+        }
+        if value.is_none() {
+            if let Some(close) = closing_quotes(c) {
+                value = self.read_quoted(close);
+            }
+        }
+        let value = value.unwrap_or_else(|| self.read_word());
+
+        let end = self.chars.peek().map_or(self.content.len(), |&(i, _)| i);
+        Some(Token { value, span: start..end })
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+    }
+
+    fn read_fenced(&mut self, start: usize) -> Option<String> {
+        let rest = &self.content[start..];
+        let end = start + FENCE.len() + rest[FENCE.len()..].find(FENCE)? + FENCE.len();
+        while self.chars.next_if(|(i, _)| *i < end).is_some() {}
+        Some(self.content[start..end].to_owned())
+    }
+
+    fn read_quoted(&mut self, close: &[char]) -> Option<String> {
+        // work on a copy so an unterminated quote can fall back to a plain word
+        let mut chars = self.chars.clone();
+        chars.next();
+
+        let mut token = String::new();
+        while let Some((_, c)) = chars.next() {
+            match c {
+                '\\' => match chars.peek() {
+                    Some(&(_, next)) if is_escapable(next) => {
+                        token.push(next);
+                        chars.next();
+                    },
+                    _ => token.push(c),
+                },
+                c if close.contains(&c) => {
+                    self.chars = chars;
+                    return Some(token);
+                },
+                c => token.push(c),
+            }
+        }
+        None
+    }
+
+    fn read_word(&mut self) -> String {
+        let mut token = String::new();
+        while let Some((_, c)) = self.chars.next_if(|(_, c)| !c.is_whitespace()) {
+            if c == '\\' {
+                if let Some((_, next)) = self.chars.next_if(|(_, next)| is_escapable(*next)) {
+                    token.push(next);
+                    continue;
+                }
+            }
+            token.push(c);
+        }
+        token
+    }
+}
+
+impl<'a> Iterator for Tokenizer<'a> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_token().map(|token| token.value)
+    }
+}
+
+fn closing_quotes(open: char) -> Option<&'static [char]> {
+    match open {
+        '"' => Some(&['"']),
+        '\'' => Some(&['\'']),
+        '“' | '„' => Some(&['”', '“']),
+        '‘' | '‚' => Some(&['’', '‘']),
+        _ => None,
+    }
+}
+
+fn is_escapable(c: char) -> bool {
+    c == '\\' || c.is_whitespace() || closing_quotes(c).is_some() || matches!(c, '”' | '’')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_on_whitespace() {
+        assert_eq!(tokenize("  kick  bob\tnow\n"), ["kick", "bob", "now"]);
+        assert!(tokenize("   ").is_empty());
+    }
+
+    #[test]
+    fn quoted_tokens() {
+        assert_eq!(tokenize(r#"say "hello world" 'a b'"#), ["say", "hello world", "a b"]);
+        assert_eq!(tokenize("say “smart quotes” ‚low‘"), ["say", "smart quotes", "low"]);
+        assert_eq!(tokenize(r#"say "" x"#), ["say", "", "x"]);
+    }
+
+    #[test]
+    fn unterminated_quote_is_a_word() {
+        assert_eq!(tokenize(r#"say "hello world"#), ["say", "\"hello", "world"]);
+        assert_eq!(tokenize("it's fine"), ["it's", "fine"]);
+    }
+
+    #[test]
+    fn escapes() {
+        assert_eq!(tokenize(r#"a\ b \"c\" d\\e f\g"#), ["a b", "\"c\"", "d\\e", "f\\g"]);
+        assert_eq!(tokenize(r#""say \"hi\"""#), ["say \"hi\""]);
+    }
+
+    #[test]
+    fn code_blocks_are_verbatim() {
+        let content = "eval ```rust\nlet a = \"b c\";\n``` after";
+        assert_eq!(tokenize(content), ["eval", "```rust\nlet a = \"b c\";\n```", "after"]);
+        // without a closing fence, the backticks are a regular word
+        assert_eq!(tokenize("```a b"), ["```a", "b"]);
+    }
+
+    #[test]
+    fn spans_include_quotes() {
+        let content = "kick  \"bob smith\" é";
+        let tokens = tokenize_spanned(content);
+        let spans = tokens.iter().map(|token| token.span.clone()).collect::<Vec<_>>();
+        assert_eq!(spans, [0..4, 6..17, 18..20]);
+        assert_eq!(&content[tokens[1].span.clone()], "\"bob smith\"");
+        assert_eq!(tokens[1].value, "bob smith");
+    }
+
+    #[test]
+    fn argument_tokens() {
+        let content = "a b";
+        let tokens = tokenize_spanned(content);
+        let token = ArgumentToken::with_source(&tokens[1], content);
+        assert_eq!((token.value, token.span, token.source), ("b", Some(2..3), Some(content)));
+
+        let token = ArgumentToken::from("c");
+        assert_eq!((token.value, token.span, token.source), ("c", None, None));
+    }
+}
@@ -1,8 +1,14 @@
 use std::fmt;
+use std::ops::Range;
 
 use async_trait::async_trait;
+use serenity::client::Context;
+use serenity::model::channel::Message;
 
 use crate::arguments::parse::{ArgumentParseContext, ArgumentParseError};
+use crate::arguments::resolve::ResolveStrategy;
+use crate::arguments::tokenize::{tokenize_spanned, ArgumentToken};
+use crate::help::CommandHelp;
 
 #[async_trait]
 pub trait CommandArguments
@@ -15,22 +21,518 @@ where
         ctx: ArgumentParseContext<'a>,
     ) -> Result<Self, CommandArgumentsReadError>
     where
-        I: Iterator<Item = &'a str> + Send;
+        I: Iterator + Send,
+        I::Item: Into<ArgumentToken<'a>>;
+
+    /// Usage of the command, e.g. `test kick <target> [reason...]`.
+    fn usage() -> &'static str;
+
+    /// Help of the command and its sub-commands.
+    fn help() -> CommandHelp;
 }
 
+/// Error returned by command handlers.
+pub type CommandError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Command invoked with a message, usually implemented with `#[polecen::command]`.
+#[async_trait]
+pub trait Command {
+    /// Arguments of the command, their help gives the name and aliases of the command.
+    type Arguments: CommandArguments + Send;
+
+    /// Handle an invocation of the command.
+    async fn run(
+        ctx: &Context,
+        message: &Message,
+        args: Self::Arguments,
+    ) -> Result<(), CommandError>;
+
+    /// Handle arguments which can't be read, `content` is the one given to
+    /// [`invoke`](Self::invoke).
+    ///
+    /// Defaults to returning the error.
+    async fn on_error(
+        _ctx: &Context,
+        _message: &Message,
+        _content: &str,
+        error: CommandArgumentsReadError,
+    ) -> Result<(), CommandError> {
+        Err(Box::new(error))
+    }
+
+    /// Read the arguments from `content`, the part of the message content after the
+    /// command name, resolving entities following `strategy`, then run the command.
+    async fn invoke(
+        ctx: &Context,
+        message: &Message,
+        content: &str,
+        strategy: ResolveStrategy,
+    ) -> Result<(), CommandError> {
+        let tokens = tokenize_spanned(content);
+        let args = tokens.iter().map(|token| ArgumentToken::with_source(token, content));
+        let parse_ctx = ArgumentParseContext::from_message(ctx, message).with_strategy(strategy);
+        match Self::Arguments::read_arguments(args, 0, parse_ctx).await {
+            Ok(args) => Self::run(ctx, message, args).await,
+            Err(error) => Self::on_error(ctx, message, content, error).await,
+        }
+    }
+}
+
+/// Error while reading command arguments.
+///
+/// `command` is the full name of the (sub-)command being read, e.g. `test kick`.
+/// `span` is the byte range of the offending token in the content, when known.
 #[derive(Clone, Debug)]
 pub enum CommandArgumentsReadError {
-    RequiredArgumentMissing { position: u8, name: String },
-    MissingSubcommand { position: u8 },
-    UnknownSubcommand { position: u8, given: String },
-    ValueParseError { position: u8, inner: ArgumentParseError },
+    RequiredArgumentMissing {
+        position: u8,
+        command: String,
+        name: String,
+    },
+    MissingSubcommand {
+        position: u8,
+        command: String,
+    },
+    UnknownSubcommand {
+        position: u8,
+        span: Option<Range<usize>>,
+        command: String,
+        given: String,
+        /// Known subcommand names and aliases close to `given`, closest first.
+        suggestions: Vec<String>,
+    },
+    /// The given subcommand is a prefix of several subcommands.
+    AmbiguousSubcommand {
+        position: u8,
+        span: Option<Range<usize>>,
+        command: String,
+        given: String,
+        /// Names of the subcommands matching `given`.
+        candidates: Vec<String>,
+    },
+    UnknownOption {
+        position: u8,
+        span: Option<Range<usize>>,
+        command: String,
+        given: String,
+    },
+    ValueParseError {
+        position: u8,
+        span: Option<Range<usize>>,
+        command: String,
+        /// Name of the argument.
+        name: String,
+        /// Raw value given for the argument.
+        value: String,
+        /// User-facing name of the argument type, see `ArgumentType::type_name`.
+        type_name: String,
+        inner: ArgumentParseError,
+    },
+    /// Less values than required were given to a `Vec<T>` argument.
+    NotEnoughValues {
+        position: u8,
+        command: String,
+        name: String,
+        min: usize,
+        given: usize,
+    },
+    /// More values than allowed were given to a `Vec<T>` argument,
+    /// `span` covers the extra values.
+    TooManyValues {
+        position: u8,
+        span: Option<Range<usize>>,
+        command: String,
+        name: String,
+        max: usize,
+        given: usize,
+    },
+}
+
+impl CommandArgumentsReadError {
+    /// Position of the token which caused the error.
+    pub fn position(&self) -> u8 {
+        match self {
+            Self::RequiredArgumentMissing { position, .. }
+            | Self::MissingSubcommand { position, .. }
+            | Self::UnknownSubcommand { position, .. }
+            | Self::AmbiguousSubcommand { position, .. }
+            | Self::UnknownOption { position, .. }
+            | Self::ValueParseError { position, .. }
+            | Self::NotEnoughValues { position, .. }
+            | Self::TooManyValues { position, .. } => *position,
+        }
+    }
+
+    /// Byte range of the token which caused the error, if known.
+    ///
+    /// Missing arguments, subcommands and values don't have a span, they are located past the end.
+    pub fn span(&self) -> Option<Range<usize>> {
+        match self {
+            Self::UnknownSubcommand { span, .. }
+            | Self::AmbiguousSubcommand { span, .. }
+            | Self::UnknownOption { span, .. }
+            | Self::ValueParseError { span, .. }
+            | Self::TooManyValues { span, .. } => span.clone(),
+            Self::RequiredArgumentMissing { .. }
+            | Self::MissingSubcommand { .. }
+            | Self::NotEnoughValues { .. } => None,
+        }
+    }
+
+    /// Render the error as a diagnostic reproducing the invocation in a code block,
+    /// with the offending token underlined.
+    ///
+    /// `content` must be the content the arguments were read from,
+    /// see [`tokenize_spanned`](crate::arguments::tokenize::tokenize_spanned).
+    /// Falls back to the error message alone when the location of the error is unknown.
+    pub fn render(&self, content: &str) -> String {
+        let (span, past_end) = match self {
+            Self::RequiredArgumentMissing { .. }
+            | Self::MissingSubcommand { .. }
+            | Self::NotEnoughValues { .. } => {
+                let end = content.trim_end().len();
+                (end..end, true)
+            },
+            _ => match self.span() {
+                Some(span) => (span, false),
+                None => return self.to_string(),
+            },
+        };
+        let (before, token) = match (content.get(..span.start), content.get(span.clone())) {
+            (Some(before), Some(token)) => (before, token),
+            _ => return self.to_string(),
+        };
+
+        // only the line where the token starts is reproduced
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        let line_end = content[span.start..].find('\n').map_or(content.len(), |i| span.start + i);
+        let line = &content[line_start..line_end];
+        let token = token.split('\n').next().unwrap_or_default();
+
+        let mut offset = before[line_start..].chars().count();
+        if past_end && offset > 0 {
+            offset += 1;
+        }
+        let width = token.chars().count().max(1);
+        format!(
+            "{}\n```\n{}\n{}{}\n```",
+            self,
+            line.replace("```", "`\u{200b}``"),
+            " ".repeat(offset),
+            "^".repeat(width)
+        )
+    }
+
+    /// Full name of the command being read when the error occurred.
+    pub fn command(&self) -> &str {
+        match self {
+            Self::RequiredArgumentMissing { command, .. }
+            | Self::MissingSubcommand { command, .. }
+            | Self::UnknownSubcommand { command, .. }
+            | Self::AmbiguousSubcommand { command, .. }
+            | Self::UnknownOption { command, .. }
+            | Self::ValueParseError { command, .. }
+            | Self::NotEnoughValues { command, .. }
+            | Self::TooManyValues { command, .. } => command,
+        }
+    }
 }
 
-impl std::error::Error for CommandArgumentsReadError {}
+impl std::error::Error for CommandArgumentsReadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::ValueParseError { inner, .. } => Some(inner),
+            _ => None,
+        }
+    }
+}
 
 impl fmt::Display for CommandArgumentsReadError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{:?}", self)?;
-        Ok(())
+        match self {
+            Self::RequiredArgumentMissing { command, name, .. } => {
+                write!(f, "Missing argument `{}` for `{}`.", name, command)
+            },
+            Self::MissingSubcommand { command, .. } => {
+                write!(f, "Missing subcommand for `{}`.", command)
+            },
+            Self::UnknownSubcommand { command, given, suggestions, .. } => {
+                write!(f, "Unknown subcommand `{}` for `{}`.", given, command)?;
+                if !suggestions.is_empty() {
+                    write!(f, " Did you mean {}?", or_list(suggestions))?;
+                }
+                Ok(())
+            },
+            Self::AmbiguousSubcommand { command, given, candidates, .. } => {
+                write!(
+                    f,
+                    "Ambiguous subcommand `{}` for `{}`, could be {}.",
+                    given,
+                    command,
+                    or_list(candidates)
+                )
+            },
+            Self::UnknownOption { command, given, .. } => {
+                write!(f, "Unknown option `{}` for `{}`.", given, command)
+            },
+            Self::ValueParseError { name, value, type_name, inner, .. } => {
+                write!(
+                    f,
+                    "Argument `{}` ({}): `{}` is not a valid {}. {}",
+                    name, type_name, value, type_name, inner
+                )
+            },
+            Self::NotEnoughValues { command, name, min, given, .. } => {
+                write!(
+                    f,
+                    "Not enough values for argument `{}` of `{}`, expected at least {} but got {}.",
+                    name, command, min, given
+                )
+            },
+            Self::TooManyValues { command, name, max, given, .. } => {
+                write!(
+                    f,
+                    "Too many values for argument `{}` of `{}`, expected at most {} but got {}.",
+                    name, command, max, given
+                )
+            },
+        }
+    }
+}
+
+/// Error to report among the errors of several arguments, the one with the lowest position,
+/// `None` if there is no error.
+///
+/// Generated readers parse arguments concurrently and use this to report the same error
+/// as if they were parsed in order.
+#[doc(hidden)]
+pub fn first_error(
+    errors: impl IntoIterator<Item = Option<CommandArgumentsReadError>>,
+) -> Option<CommandArgumentsReadError> {
+    // `min_by_key` keeps the first of equal positions
+    errors.into_iter().flatten().min_by_key(|e| e.position())
+}
+
+/// Format names as "`a`, `b` or `c`".
+fn or_list(names: &[String]) -> String {
+    let mut names = names.iter().map(|name| format!("`{}`", name)).collect::<Vec<_>>();
+    match names.pop() {
+        Some(last) if !names.is_empty() => format!("{} or {}", names.join(", "), last),
+        Some(last) => last,
+        None => String::new(),
+    }
+}
+
+/// Find the names close to `given` among `candidates`, closest first.
+///
+/// Used to suggest subcommands when an unknown one is given.
+/// The comparison is case-insensitive and allows roughly one typo per three characters.
+pub fn suggestions(given: &str, candidates: &[&str]) -> Vec<String> {
+    let given = given.to_lowercase();
+    let max_distance = (given.chars().count() / 3).max(1);
+
+    let mut matches = candidates
+        .iter()
+        .map(|candidate| (edit_distance(&given, &candidate.to_lowercase()), *candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .collect::<Vec<_>>();
+    // stable, candidates keep their declaration order on ties
+    matches.sort_by_key(|(distance, _)| *distance);
+    matches.into_iter().map(|(_, candidate)| candidate.to_owned()).collect()
+}
+
+/// Resolve a given subcommand name to a name of the pattern of a child.
+///
+/// `children` are the names and aliases of each child. An exact match always wins,
+/// then names are compared regardless of ASCII case if `ignore_case` is set,
+/// then unique prefixes are accepted if `prefix` is set.
+/// Returns `given` itself if nothing matches, and the first name of each matching child
+/// if a prefix matches several children.
+pub fn resolve_subcommand<'a>(
+    given: &'a str,
+    children: &[&[&'a str]],
+    ignore_case: bool,
+    prefix: bool,
+) -> Result<&'a str, Vec<String>> {
+    let names = || children.iter().flat_map(|names| names.iter().copied());
+    if names().any(|name| name == given) {
+        return Ok(given);
+    }
+    if ignore_case {
+        if let Some(name) = names().find(|name| name.eq_ignore_ascii_case(given)) {
+            return Ok(name);
+        }
+    }
+    if prefix && !given.is_empty() {
+        let is_prefix = |name: &str| match name.get(..given.len()) {
+            Some(start) if ignore_case => start.eq_ignore_ascii_case(given),
+            Some(start) => start == given,
+            None => false,
+        };
+        let matching = children
+            .iter()
+            .filter_map(|names| names.iter().copied().find(|name| is_prefix(name)))
+            .collect::<Vec<_>>();
+        match matching.as_slice() {
+            [] => {},
+            [name] => return Ok(name),
+            _ => {
+                let candidates = children
+                    .iter()
+                    .filter(|names| names.iter().any(|name| is_prefix(name)))
+                    .filter_map(|names| names.first())
+                    .map(|name| String::from(*name));
+                return Err(candidates.collect());
+            },
+        }
+    }
+    Ok(given)
+}
+
+/// Optimal string alignment distance, Levenshtein distance with adjacent transpositions.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a = a.chars().collect::<Vec<_>>();
+    let b = b.chars().collect::<Vec<_>>();
+
+    // rows i - 2, i - 1 and i of the distance matrix
+    let mut prev_prev = vec![0; b.len() + 1];
+    let mut prev = (0..=b.len()).collect::<Vec<_>>();
+    let mut current = vec![0; b.len() + 1];
+    for i in 1..=a.len() {
+        current[0] = i;
+        for j in 1..=b.len() {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            current[j] = (prev[j] + 1).min(current[j - 1] + 1).min(prev[j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                current[j] = current[j].min(prev_prev[j - 2] + 1);
+            }
+        }
+        std::mem::swap(&mut prev_prev, &mut prev);
+        std::mem::swap(&mut prev, &mut current);
+    }
+    prev[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edit_distances() {
+        assert_eq!(edit_distance("", ""), 0);
+        assert_eq!(edit_distance("kick", "kick"), 0);
+        assert_eq!(edit_distance("", "ban"), 3);
+        assert_eq!(edit_distance("kick", "kik"), 1);
+        assert_eq!(edit_distance("kick", "kicks"), 1);
+        assert_eq!(edit_distance("kick", "lick"), 1);
+        assert_eq!(edit_distance("kick", "ikck"), 1);
+        assert_eq!(edit_distance("ca", "abc"), 3);
+        assert_eq!(edit_distance("héllo", "hello"), 1);
+    }
+
+    #[test]
+    fn suggestions_closest_first() {
+        let candidates = ["kick", "ban", "unban", "kickall"];
+        assert_eq!(suggestions("kik", &candidates), ["kick"]);
+        assert_eq!(suggestions("KICK", &candidates), ["kick"]);
+        assert_eq!(suggestions("bann", &candidates), ["ban"]);
+        assert_eq!(suggestions("unbna", &candidates), ["unban"]);
+        assert_eq!(suggestions("kickal", &candidates), ["kickall", "kick"]);
+        assert!(suggestions("mute", &candidates).is_empty());
+    }
+
+    #[test]
+    fn resolve_subcommands() {
+        let children: &[&[&str]] = &[&["kick", "k"], &["kickall"], &["ban", "b"], &["Unban"]];
+        assert_eq!(resolve_subcommand("kick", children, false, false), Ok("kick"));
+        assert_eq!(resolve_subcommand("k", children, false, true), Ok("k"));
+        assert_eq!(resolve_subcommand("KICK", children, false, false), Ok("KICK"));
+        assert_eq!(resolve_subcommand("KICK", children, true, false), Ok("kick"));
+        assert_eq!(resolve_subcommand("ba", children, false, false), Ok("ba"));
+        assert_eq!(resolve_subcommand("ba", children, false, true), Ok("ban"));
+        assert_eq!(resolve_subcommand("un", children, false, true), Ok("un"));
+        assert_eq!(resolve_subcommand("un", children, true, true), Ok("Unban"));
+        assert_eq!(
+            resolve_subcommand("kic", children, false, true),
+            Err(vec!["kick".to_owned(), "kickall".to_owned()])
+        );
+        assert_eq!(resolve_subcommand("", children, false, true), Ok(""));
+        assert_eq!(resolve_subcommand("mute", children, true, true), Ok("mute"));
+    }
+
+    #[test]
+    fn messages() {
+        let error = CommandArgumentsReadError::UnknownSubcommand {
+            position: 0,
+            span: None,
+            command: "test".to_owned(),
+            given: "kik".to_owned(),
+            suggestions: vec!["kick".to_owned(), "k".to_owned(), "kickall".to_owned()],
+        };
+        assert_eq!(
+            error.to_string(),
+            "Unknown subcommand `kik` for `test`. Did you mean `kick`, `k` or `kickall`?"
+        );
+        assert_eq!(error.render("kik"), error.to_string());
+    }
+
+    #[test]
+    fn render_underlines_token() {
+        let content = "kick \"bob smith\" now";
+        let error = CommandArgumentsReadError::ValueParseError {
+            position: 1,
+            span: Some(5..16),
+            command: "test kick".to_owned(),
+            name: "target".to_owned(),
+            value: "bob smith".to_owned(),
+            type_name: "member".to_owned(),
+            inner: ArgumentParseError::NotFound,
+        };
+        assert_eq!(
+            error.render(content),
+            "Argument `target` (member): `bob smith` is not a valid member. \
+             No matching entity was found.\n\
+             ```\nkick \"bob smith\" now\n     ^^^^^^^^^^^\n```"
+        );
+    }
+
+    #[test]
+    fn render_missing_past_end() {
+        let error = CommandArgumentsReadError::RequiredArgumentMissing {
+            position: 1,
+            command: "test kick".to_owned(),
+            name: "target".to_owned(),
+        };
+        assert_eq!(
+            error.render("kick  "),
+            "Missing argument `target` for `test kick`.\n```\nkick  \n     ^\n```"
+        );
+        assert_eq!(error.render(""), "Missing argument `target` for `test kick`.\n```\n\n^\n```");
+    }
+
+    #[test]
+    fn render_reproduces_one_line() {
+        let content = "eval ```\nlet a = b;\n``` ö x";
+        let error = CommandArgumentsReadError::UnknownOption {
+            position: 2,
+            span: Some(content.find('x').unwrap()..content.len()),
+            command: "eval".to_owned(),
+            given: "x".to_owned(),
+        };
+        assert_eq!(
+            error.render(content),
+            "Unknown option `x` for `eval`.\n```\n`\u{200b}`` ö x\n      ^\n```"
+        );
+
+        let error = CommandArgumentsReadError::UnknownOption {
+            position: 1,
+            span: Some(5..content.len()),
+            command: "eval".to_owned(),
+            given: "x".to_owned(),
+        };
+        assert_eq!(
+            error.render(content),
+            "Unknown option `x` for `eval`.\n```\neval `\u{200b}``\n     ^^^\n```"
+        );
     }
 }
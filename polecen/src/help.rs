@@ -0,0 +1,76 @@
+//! # Commands help
+//! Help and usage of expanded commands, built from the patterns and descriptions
+//! given to the expand macro.
+
+use std::fmt;
+
+/// Help of a command (or sub-command).
+#[derive(Clone, Debug)]
+pub struct CommandHelp {
+    /// Name of the command, first element of its pattern.
+    pub name: &'static str,
+    /// Other elements of the pattern.
+    pub aliases: Vec<&'static str>,
+    pub description: Option<&'static str>,
+    /// Usage of the command, including the names of the parent commands.
+    pub usage: &'static str,
+    pub arguments: Vec<ArgumentHelp>,
+    pub subcommands: Vec<CommandHelp>,
+}
+
+/// Help of a command argument.
+#[derive(Clone, Debug)]
+pub struct ArgumentHelp {
+    pub name: &'static str,
+    /// User-facing name of the argument type, the same as in errors,
+    /// see `ArgumentType::type_name`.
+    pub type_name: String,
+    pub description: Option<&'static str>,
+    pub required: bool,
+    /// Default value of the argument, as written in the expand macro.
+    pub default: Option<&'static str>,
+}
+
+impl fmt::Display for CommandHelp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Usage: {}", self.usage)?;
+        if let Some(description) = self.description {
+            writeln!(f, "{}", description)?;
+        }
+        if !self.aliases.is_empty() {
+            writeln!(f, "Aliases: {}", self.aliases.join(", "))?;
+        }
+
+        if !self.arguments.is_empty() {
+            writeln!(f, "\nArguments:")?;
+            for argument in &self.arguments {
+                write!(f, "  {} ({}", argument.name, argument.type_name)?;
+                if let Some(default) = argument.default {
+                    write!(f, ", default: {}", default)?;
+                } else if !argument.required {
+                    write!(f, ", optional")?;
+                }
+                write!(f, ")")?;
+                if let Some(description) = argument.description {
+                    write!(f, " - {}", description)?;
+                }
+                writeln!(f)?;
+            }
+        }
+
+        if !self.subcommands.is_empty() {
+            writeln!(f, "\nSubcommands:")?;
+            for subcommand in &self.subcommands {
+                write!(f, "  {}", subcommand.name)?;
+                if !subcommand.aliases.is_empty() {
+                    write!(f, " ({})", subcommand.aliases.join(", "))?;
+                }
+                if let Some(description) = subcommand.description {
+                    write!(f, " - {}", description)?;
+                }
+                writeln!(f)?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,61 @@
+//! # Interactions support
+//! Available with the feature `interactions`, this mod allows expanded commands
+//! to be registered and used as application commands (slash commands).
+
+use async_trait::async_trait;
+use serde_json::Value;
+pub use serenity::model::interactions::application_command::{
+    ApplicationCommandInteraction,
+    ApplicationCommandInteractionData,
+    ApplicationCommandInteractionDataOption,
+    ApplicationCommandInteractionDataOptionValue,
+    ApplicationCommandOptionType,
+};
+
+use crate::arguments::parse::ArgumentParseContext;
+use crate::command::CommandArgumentsReadError;
+
+/// Arguments structure that can be described as application command options
+/// and read from an application command interaction.
+///
+/// Implemented by `expand_command_here!` for every generated structure.
+#[async_trait]
+pub trait InteractionArguments
+where
+    Self: Sized,
+{
+    /// Options of the application command (or sub-command) matching this structure,
+    /// as expected by Discord API.
+    fn application_command_options() -> Vec<Value>;
+
+    /// Whether the options are subcommands, nested structures are then
+    /// exposed as a sub-command group.
+    fn has_subcommands() -> bool {
+        false
+    }
+
+    /// Read arguments from the options of an interaction.
+    /// Options are matched by name, `position` is the index of the argument.
+    async fn read_interaction<'a>(
+        options: &'a [ApplicationCommandInteractionDataOption],
+        position: u8,
+        ctx: ArgumentParseContext<'a>,
+    ) -> Result<Self, CommandArgumentsReadError>;
+
+    /// Read arguments from the data of an application command interaction.
+    async fn read_interaction_data<'a>(
+        data: &'a ApplicationCommandInteractionData,
+        ctx: ArgumentParseContext<'a>,
+    ) -> Result<Self, CommandArgumentsReadError> {
+        Self::read_interaction(&data.options, 0, ctx).await
+    }
+}
+
+/// Raw value of an option, as it would have been typed in a message.
+pub fn option_raw_value(option: &ApplicationCommandInteractionDataOption) -> Option<String> {
+    match option.value.as_ref()? {
+        Value::String(value) => Some(value.clone()),
+        Value::Null => None,
+        value => Some(value.to_string()),
+    }
+}
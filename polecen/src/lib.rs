@@ -1,11 +1,22 @@
 pub mod arguments;
 pub mod command;
+pub mod help;
+#[cfg(interactions)]
+pub mod interactions;
+pub mod router;
+#[cfg(testing)]
+pub mod testing;
 
 #[cfg(feature = "macros")]
 pub mod macros;
 
 pub use async_trait::async_trait;
+pub use futures;
 pub use polecen_macros::*;
+#[cfg(interactions)]
+pub use serde_json;
+pub use serenity;
 
 #[cfg(feature = "macros")]
+#[allow(unused_imports)]
 pub use crate::macros::*;
@@ -1,5 +1,24 @@
 #[macro_export]
 macro_rules! read_args {
+    ($ty:ty, [I] $interaction:expr, $ctx:expr) => {{
+        let interaction = &$interaction;
+        <$ty as ::polecen::interactions::InteractionArguments>::read_interaction_data(
+            &interaction.data,
+            ::polecen::arguments::parse::ArgumentParseContext::from_interaction($ctx, interaction),
+        )
+        .await
+    }};
+    ($ty:ty, [C] $content:expr, $($rest:tt)+) => {{
+        let content: &str = &$content;
+        let tokens = ::polecen::arguments::tokenize::tokenize_spanned(content);
+        let args = tokens
+            .iter()
+            .map(|token| ::polecen::arguments::tokenize::ArgumentToken::with_source(token, content));
+        ::polecen::read_args!($ty, args, $($rest)+)
+    }};
+    ($ty:ty, $args:expr, [P] $parse_ctx:expr) => {
+        <$ty>::read_arguments($args, 0, $parse_ctx).await
+    };
     ($ty:ty, $args:expr, $ctx:expr, $guild_id:expr) => {
         <$ty>::read_arguments(
             $args,
@@ -9,6 +28,11 @@ macro_rules! read_args {
         .await
     };
     ($ty:ty, $args:expr, $ctx:expr, [M] $message:ident) => {
-        ::polecen::read_args!($ty, $args, $ctx, $message.guild_id)
+        <$ty>::read_arguments(
+            $args,
+            0,
+            ::polecen::arguments::parse::ArgumentParseContext::from_message($ctx, &$message),
+        )
+        .await
     };
 }
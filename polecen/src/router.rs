@@ -0,0 +1,308 @@
+//! # Command router
+//! Routes messages to commands by their name, without serenity's `StandardFramework`.
+//!
+//! Commands are registered by the name and aliases of their root pattern, either
+//! as a [`Command`] or as a [`Group`] whose subcommands are handled by a handler.
+//! A message is a command if it starts with one of the prefixes of the router:
+//!
+//! ```ignore
+//! let router = Router::new()
+//!     .prefix("!")
+//!     .mention(true)
+//!     .command::<CalcCommand>()
+//!     .group::<TestCommandArgs>(Arc::new(Commands));
+//!
+//! // in EventHandler::message
+//! router.dispatch(&ctx, &message).await?;
+//! ```
+
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use futures::future::BoxFuture;
+use serenity::client::Context;
+use serenity::model::channel::Message;
+use serenity::model::id::UserId;
+
+use crate::arguments::resolve::ResolveStrategy;
+use crate::command::{resolve_subcommand, Command, CommandArguments, CommandError};
+use crate::help::CommandHelp;
+
+/// Parent command whose subcommands are routed to a handler.
+///
+/// Implemented by `expand_command_here!` for every generated enum, the handler
+/// is then the generated handler trait, e.g. `dyn TestCommandArgsHandler`.
+#[async_trait]
+pub trait Group: CommandArguments {
+    type Handler: ?Sized + Send + Sync;
+
+    /// Read the arguments from `content`, the part of the message content after the
+    /// command name, resolving entities following `strategy`, then route them to `handler`.
+    async fn dispatch(
+        handler: &Self::Handler,
+        ctx: &Context,
+        message: &Message,
+        content: &str,
+        strategy: ResolveStrategy,
+    ) -> Result<(), CommandError>;
+}
+
+/// Prefix of a guild, `None` to use the static prefixes.
+pub type DynamicPrefix = Box<
+    dyn for<'fut> Fn(&'fut Context, &'fut Message) -> BoxFuture<'fut, Option<String>> + Send + Sync,
+>;
+
+/// Command registered in a router.
+#[async_trait]
+trait Route: Send + Sync {
+    async fn invoke(
+        &self,
+        ctx: &Context,
+        message: &Message,
+        content: &str,
+        strategy: ResolveStrategy,
+    ) -> Result<(), CommandError>;
+}
+
+struct CommandRoute<C>(PhantomData<fn() -> C>);
+
+#[async_trait]
+impl<C: Command + Send + 'static> Route for CommandRoute<C> {
+    async fn invoke(
+        &self,
+        ctx: &Context,
+        message: &Message,
+        content: &str,
+        strategy: ResolveStrategy,
+    ) -> Result<(), CommandError> {
+        C::invoke(ctx, message, content, strategy).await
+    }
+}
+
+struct GroupRoute<T: Group>(Arc<T::Handler>);
+
+#[async_trait]
+impl<T: Group + 'static> Route for GroupRoute<T> {
+    async fn invoke(
+        &self,
+        ctx: &Context,
+        message: &Message,
+        content: &str,
+        strategy: ResolveStrategy,
+    ) -> Result<(), CommandError> {
+        T::dispatch(&self.0, ctx, message, content, strategy).await
+    }
+}
+
+/// Router of messages to commands.
+///
+/// Prefixes are checked in order: the mention of the bot if [`mention`](Self::mention)
+/// is set, the prefix of the guild if [`dynamic_prefix`](Self::dynamic_prefix) returns one,
+/// otherwise the static prefixes. Whitespace is allowed after the prefix.
+pub struct Router {
+    prefixes: Vec<String>,
+    dynamic_prefix: Option<DynamicPrefix>,
+    mention: bool,
+    ignore_case: bool,
+    ignore_bots: bool,
+    strategy: ResolveStrategy,
+    routes: Vec<(CommandHelp, Box<dyn Route>)>,
+}
+
+impl Default for Router {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Router {
+    pub fn new() -> Self {
+        Self {
+            prefixes: Vec::new(),
+            dynamic_prefix: None,
+            mention: false,
+            ignore_case: false,
+            ignore_bots: true,
+            strategy: ResolveStrategy::default(),
+            routes: Vec::new(),
+        }
+    }
+
+    /// Add a static prefix, e.g. `!`.
+    pub fn prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefixes.push(prefix.into());
+        self
+    }
+
+    /// Set the callback giving the prefix of the guild (or the direct messages)
+    /// a message is sent in, e.g. from a database.
+    ///
+    /// ```ignore
+    /// router.dynamic_prefix(|ctx, message| Box::pin(async move {
+    ///     prefixes.get(message.guild_id?).await
+    /// }))
+    /// ```
+    pub fn dynamic_prefix<F>(mut self, dynamic_prefix: F) -> Self
+    where
+        F: for<'fut> Fn(&'fut Context, &'fut Message) -> BoxFuture<'fut, Option<String>>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.dynamic_prefix = Some(Box::new(dynamic_prefix));
+        self
+    }
+
+    /// Accept a mention of the bot as a prefix, e.g. `@bot calc 1 + 2`.
+    ///
+    /// The id of the bot is read from the cache, it is fetched with HTTP
+    /// for each mention without the `cache` feature.
+    pub fn mention(mut self, mention: bool) -> Self {
+        self.mention = mention;
+        self
+    }
+
+    /// Compare command names regardless of ASCII case.
+    pub fn ignore_case(mut self, ignore_case: bool) -> Self {
+        self.ignore_case = ignore_case;
+        self
+    }
+
+    /// Ignore messages sent by bots, set by default.
+    pub fn ignore_bots(mut self, ignore_bots: bool) -> Self {
+        self.ignore_bots = ignore_bots;
+        self
+    }
+
+    /// Resolution strategy of the entities given as arguments, `CacheThenHttp` by default.
+    pub fn strategy(mut self, strategy: ResolveStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// Register a command, usually generated by `#[polecen::command]`.
+    ///
+    /// # Panics
+    ///
+    /// If the name or an alias of the command is already registered.
+    pub fn command<C: Command + Send + 'static>(self) -> Self {
+        self.route(C::Arguments::help(), Box::new(CommandRoute::<C>(PhantomData)))
+    }
+
+    /// Register a parent command generated by `expand_command_here!`, its subcommands
+    /// are routed to `handler`.
+    ///
+    /// # Panics
+    ///
+    /// If the name or an alias of the command is already registered.
+    pub fn group<T: Group + 'static>(self, handler: Arc<T::Handler>) -> Self {
+        self.route(T::help(), Box::new(GroupRoute::<T>(handler)))
+    }
+
+    fn route(mut self, help: CommandHelp, route: Box<dyn Route>) -> Self {
+        for name in std::iter::once(&help.name).chain(&help.aliases) {
+            if let Some((other, _)) = self.find(name) {
+                panic!("`{}` is already registered by `{}`", name, other.name);
+            }
+        }
+        self.routes.push((help, route));
+        self
+    }
+
+    /// Help of the registered commands, in registration order.
+    pub fn commands(&self) -> impl Iterator<Item = &CommandHelp> {
+        self.routes.iter().map(|(help, _)| help)
+    }
+
+    fn find(&self, name: &str) -> Option<&(CommandHelp, Box<dyn Route>)> {
+        let names = self
+            .routes
+            .iter()
+            .map(|(help, _)| {
+                let mut names = vec![help.name];
+                names.extend(&help.aliases);
+                names
+            })
+            .collect::<Vec<_>>();
+        let children = names.iter().map(Vec::as_slice).collect::<Vec<_>>();
+        let name = resolve_subcommand(name, &children, self.ignore_case, false).ok()?;
+        let index = names.iter().position(|names| names.contains(&name))?;
+        Some(&self.routes[index])
+    }
+
+    /// Message content after the prefix, `None` if the message doesn't start with a prefix.
+    pub async fn strip_prefix<'a>(&self, ctx: &Context, message: &'a Message) -> Option<&'a str> {
+        let content = message.content.as_str();
+        if self.mention && content.starts_with("<@") {
+            if let Some(rest) = current_user_id(ctx).await.and_then(|id| strip_mention(content, id))
+            {
+                return Some(rest.trim_start());
+            }
+        }
+        if let Some(dynamic_prefix) = &self.dynamic_prefix {
+            if let Some(prefix) = dynamic_prefix(ctx, message).await {
+                return content.strip_prefix(prefix.as_str()).map(str::trim_start);
+            }
+        }
+        self.prefixes
+            .iter()
+            .find_map(|prefix| content.strip_prefix(prefix.as_str()))
+            .map(str::trim_start)
+    }
+
+    /// Run the command invoked by a message.
+    ///
+    /// Returns false if the message is not a command: it doesn't start with a prefix,
+    /// the command is unknown or the author is ignored.
+    /// Errors are the ones returned by the command.
+    pub async fn dispatch(&self, ctx: &Context, message: &Message) -> Result<bool, CommandError> {
+        if self.ignore_bots && message.author.bot {
+            return Ok(false);
+        }
+        let content = match self.strip_prefix(ctx, message).await {
+            Some(content) => content,
+            None => return Ok(false),
+        };
+        let name = content.split(char::is_whitespace).next().unwrap_or_default();
+        match self.find(name) {
+            Some((_, route)) => {
+                route.invoke(ctx, message, &content[name.len()..], self.strategy).await?;
+                Ok(true)
+            },
+            None => Ok(false),
+        }
+    }
+}
+
+/// Id of the bot, from the cache or with an HTTP request without the `cache` feature.
+async fn current_user_id(ctx: &Context) -> Option<UserId> {
+    #[cfg(cache)]
+    return Some(ctx.cache.current_user_id().await);
+    #[cfg(not(cache))]
+    return ctx.http.get_current_user().await.ok().map(|user| user.id);
+}
+
+/// Strip a leading mention of `user_id`, either `<@id>` or `<@!id>`.
+fn strip_mention(content: &str, user_id: UserId) -> Option<&str> {
+    let rest = content.strip_prefix("<@")?;
+    let rest = rest.strip_prefix('!').unwrap_or(rest);
+    rest.strip_prefix(user_id.0.to_string().as_str())?.strip_prefix('>')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strip_mentions() {
+        let bot = UserId(42);
+        assert_eq!(strip_mention("<@42> ping", bot), Some(" ping"));
+        assert_eq!(strip_mention("<@!42>ping", bot), Some("ping"));
+        assert_eq!(strip_mention("<@42>", bot), Some(""));
+        assert_eq!(strip_mention("<@421> ping", bot), None);
+        assert_eq!(strip_mention("<@4> ping", bot), None);
+        assert_eq!(strip_mention("<@&42> ping", bot), None);
+        assert_eq!(strip_mention("ping <@42>", bot), None);
+    }
+}
@@ -0,0 +1,437 @@
+//! # Testing helpers
+//! Read command arguments without a Discord connection, entities are resolved
+//! from an in-memory [`MockResolver`].
+//!
+//! [`parse`] reads a command line against [`Fixtures`], a fake guild with members,
+//! roles and channels:
+//!
+//! ```ignore
+//! let fixtures = Fixtures::new().member(2, "bob").role(5, "Mods").author(2);
+//! let args = polecen::testing::parse::<TestCommandArgs>("kick bob", &fixtures).await?;
+//! ```
+//!
+//! Code taking serenity's `Context` and a `Message`, e.g. handlers and routers,
+//! can be given [`Fixtures::context`] and [`Fixtures::message`].
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use serenity::client::bridge::gateway::ShardMessenger;
+use serenity::client::Context;
+use serenity::http::Http;
+use serenity::model::channel::{Channel, GuildChannel, Message};
+use serenity::model::guild::{Guild, Member, Role};
+use serenity::model::id::{ChannelId, GuildId, RoleId, UserId};
+use serenity::model::user::User;
+use serenity::prelude::{RwLock, TypeMap};
+
+use crate::arguments::parse::{ArgumentParseContext, ArgumentParseError};
+use crate::arguments::resolve::{
+    has_all_members,
+    resolve,
+    EntityResolver,
+    Filter,
+    ResolveStrategy,
+};
+use crate::arguments::tokenize::{tokenize_spanned, ArgumentToken};
+use crate::command::{CommandArguments, CommandArgumentsReadError};
+
+/// Read `content` as the arguments of `T`, resolving entities from `fixtures`.
+///
+/// `content` is tokenized like `read_args!` with `[C]`, without the root command name,
+/// e.g. `kick bob` for `test kick`.
+pub async fn parse<T: CommandArguments>(
+    content: &str,
+    fixtures: &Fixtures,
+) -> Result<T, CommandArgumentsReadError> {
+    let resolver = fixtures.resolver();
+    let mut ctx = ArgumentParseContext::with_resolver(&resolver, fixtures.guild_id);
+    ctx.author_id = fixtures.author_id;
+    ctx.channel_id = fixtures.channel_id;
+
+    let tokens = tokenize_spanned(content);
+    let args = tokens.iter().map(|token| ArgumentToken::with_source(token, content));
+    T::read_arguments(args, 0, ctx).await
+}
+
+/// Entities known when reading arguments with [`parse`].
+///
+/// Members, roles and channels belong to a fake guild, which is the guild
+/// the command is invoked in unless [`dm`](Self::dm) is called.
+#[derive(Clone, Debug)]
+pub struct Fixtures {
+    pub guild_id: Option<GuildId>,
+    pub author_id: Option<UserId>,
+    pub channel_id: Option<ChannelId>,
+    users: Vec<Value>,
+    members: Vec<Value>,
+    roles: Vec<Value>,
+    channels: Vec<Value>,
+}
+
+impl Default for Fixtures {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Fixtures {
+    /// Id of the fake guild.
+    pub const GUILD_ID: GuildId = GuildId(1);
+    /// Id of the bot, the current user of [`context`](Self::context).
+    pub const BOT_ID: UserId = UserId(999);
+
+    pub fn new() -> Self {
+        Self {
+            guild_id: Some(Self::GUILD_ID),
+            author_id: None,
+            channel_id: None,
+            users: Vec::new(),
+            members: Vec::new(),
+            roles: Vec::new(),
+            channels: Vec::new(),
+        }
+    }
+
+    /// Invoke the command outside of the guild, as in direct messages.
+    pub fn dm(mut self) -> Self {
+        self.guild_id = None;
+        self
+    }
+
+    /// Invoke the command as the given user.
+    pub fn author(mut self, user_id: u64) -> Self {
+        self.author_id = Some(UserId(user_id));
+        self
+    }
+
+    /// Invoke the command in the given channel.
+    pub fn invoked_in(mut self, channel_id: u64) -> Self {
+        self.channel_id = Some(ChannelId(channel_id));
+        self
+    }
+
+    /// Add a user who isn't a member of the guild.
+    pub fn user(mut self, id: u64, name: &str) -> Self {
+        self.users.push(user_json(id, name));
+        self
+    }
+
+    /// Add a member of the guild.
+    pub fn member(self, id: u64, name: &str) -> Self {
+        self.member_with_nick(id, name, None)
+    }
+
+    /// Add a member of the guild with a nickname.
+    pub fn member_with_nick(mut self, id: u64, name: &str, nick: Option<&str>) -> Self {
+        self.members.push(json!({
+            "user": user_json(id, name),
+            "guild_id": Self::GUILD_ID,
+            "nick": nick,
+            "roles": [],
+            "joined_at": null,
+            "deaf": false,
+            "mute": false,
+        }));
+        self
+    }
+
+    /// Add a role to the guild.
+    pub fn role(mut self, id: u64, name: &str) -> Self {
+        self.roles.push(json!({
+            "id": id.to_string(),
+            "guild_id": Self::GUILD_ID,
+            "name": name,
+            "color": 0,
+            "hoist": false,
+            "managed": false,
+            "mentionable": true,
+            "permissions": "0",
+            "position": self.roles.len() + 1,
+        }));
+        self
+    }
+
+    /// Add a text channel to the guild.
+    pub fn channel(mut self, id: u64, name: &str) -> Self {
+        self.channels.push(json!({
+            "id": id.to_string(),
+            "guild_id": Self::GUILD_ID,
+            "type": 0,
+            "name": name,
+            "position": self.channels.len(),
+            "permission_overwrites": [],
+        }));
+        self
+    }
+
+    /// Build a message sent by the author in the channel the command is invoked in.
+    ///
+    /// # Panics
+    ///
+    /// If the author isn't set.
+    pub fn message(&self, content: &str) -> Message {
+        let author_id = self.author_id.expect("the author of the message isn't set");
+        let author = self
+            .members
+            .iter()
+            .map(|member| &member["user"])
+            .chain(&self.users)
+            .find(|user| user["id"].as_str() == Some(author_id.to_string().as_str()))
+            .cloned()
+            .unwrap_or_else(|| user_json(author_id.0, "author"));
+        let message = json!({
+            "id": "1",
+            "channel_id": self.channel_id.unwrap_or(ChannelId(1)),
+            "guild_id": self.guild_id,
+            "author": author,
+            "content": content,
+            "timestamp": "1970-01-01T00:00:00Z",
+            "edited_timestamp": null,
+            "tts": false,
+            "mention_everyone": false,
+            "mentions": [],
+            "mention_roles": [],
+            "attachments": [],
+            "embeds": [],
+            "pinned": false,
+            "type": 0,
+        });
+        serde_json::from_value(message).expect("invalid fixture message")
+    }
+
+    /// Build a serenity context without a Discord connection, its current user is
+    /// [`BOT_ID`](Self::BOT_ID) and the fake guild is in its cache with the `cache` feature.
+    ///
+    /// HTTP requests are made without a token, they are not expected to succeed.
+    pub async fn context(&self) -> Context {
+        #[cfg(cache)]
+        let cache = {
+            use serenity::model::event::{GuildCreateEvent, UserUpdateEvent};
+
+            let cache = serenity::cache::Cache::new();
+            let mut bot = user_json(Self::BOT_ID.0, "polecen");
+            for (field, value) in &[("bot", true), ("mfa_enabled", false), ("verified", true)] {
+                bot[*field] = Value::from(*value);
+            }
+            let mut current_user: UserUpdateEvent =
+                serde_json::from_value(bot).expect("invalid fixture bot");
+            cache.update(&mut current_user).await;
+            if self.guild_id.is_some() {
+                let mut guild: GuildCreateEvent =
+                    serde_json::from_value(self.guild_json()).expect("invalid fixture guild");
+                cache.update(&mut guild).await;
+            }
+            Arc::new(cache)
+        };
+        Context {
+            data: Arc::new(RwLock::new(TypeMap::new())),
+            shard: ShardMessenger::new(futures::channel::mpsc::unbounded().0),
+            shard_id: 0,
+            http: Arc::new(Http::new_with_token("")),
+            #[cfg(cache)]
+            cache,
+        }
+    }
+
+    /// Build the resolver of the fixtures.
+    ///
+    /// # Panics
+    ///
+    /// If the fake guild can't be deserialized, which would be a bug.
+    pub fn resolver(&self) -> MockResolver {
+        let mut resolver = MockResolver::default();
+        let guild = self.guild_json();
+        resolver.add_guild(serde_json::from_value(guild).expect("invalid fixture guild"));
+
+        for user in &self.users {
+            resolver.add_user(serde_json::from_value(user.clone()).expect("invalid fixture user"));
+        }
+        resolver
+    }
+
+    fn guild_json(&self) -> Value {
+        let mut guild = json!({
+            "id": Self::GUILD_ID,
+            "name": "polecen",
+            "owner_id": "0",
+            "region": "",
+            "preferred_locale": "en-US",
+            "joined_at": "1970-01-01T00:00:00Z",
+            "afk_timeout": 0,
+            "member_count": self.members.len(),
+            "large": false,
+            "default_message_notifications": 0,
+            "explicit_content_filter": 0,
+            "mfa_level": 0,
+            "verification_level": 0,
+            "nsfw": false,
+            "nsfw_level": 0,
+            "system_channel_flags": 0,
+        });
+        guild["members"] = Value::from(self.members.clone());
+        guild["roles"] = Value::from(self.roles.clone());
+        guild["channels"] = Value::from(self.channels.clone());
+        for field in &["emojis", "features", "presences", "voice_states"] {
+            guild[*field] = json!([]);
+        }
+        guild
+    }
+}
+
+fn user_json(id: u64, name: &str) -> Value {
+    json!({
+        "id": id.to_string(),
+        "username": name,
+        "discriminator": format!("{:04}", id % 10000),
+        "avatar": null,
+    })
+}
+
+/// In-memory entity resolver.
+///
+/// Members, roles and channels of a guild are resolved from the guild itself.
+/// Guilds stand for serenity's cache when searching entities by name: the search
+/// follows the strategy like serenity's `Context` does, and HTTP requests are
+/// answered from the same guilds and counted by [`http_requests`](Self::http_requests).
+#[derive(Clone, Debug, Default)]
+pub struct MockResolver {
+    pub users: HashMap<UserId, User>,
+    pub channels: HashMap<ChannelId, Channel>,
+    pub guilds: HashMap<GuildId, Guild>,
+    http_requests: Arc<AtomicUsize>,
+}
+
+impl MockResolver {
+    pub fn add_user(&mut self, user: User) {
+        self.users.insert(user.id, user);
+    }
+
+    /// Add a channel, guild channels are also added to their guild if it is known.
+    pub fn add_channel(&mut self, channel: Channel) {
+        if let Channel::Guild(channel) = &channel {
+            if let Some(guild) = self.guilds.get_mut(&channel.guild_id) {
+                guild.channels.insert(channel.id, channel.clone());
+            }
+        }
+        self.channels.insert(channel.id(), channel);
+    }
+
+    /// Add a guild along with the users of its members and its channels.
+    pub fn add_guild(&mut self, guild: Guild) {
+        for member in guild.members.values() {
+            self.add_user(member.user.clone());
+        }
+        for channel in guild.channels.values() {
+            self.channels.insert(channel.id, Channel::Guild(channel.clone()));
+        }
+        self.guilds.insert(guild.id, guild);
+    }
+
+    /// Number of HTTP requests made so far, shared by the clones of the resolver.
+    pub fn http_requests(&self) -> usize {
+        self.http_requests.load(Ordering::SeqCst)
+    }
+
+    fn get_guild(&self, guild_id: GuildId) -> Result<&Guild, ArgumentParseError> {
+        self.guilds.get(&guild_id).ok_or(ArgumentParseError::NotFound)
+    }
+
+    /// Search the entities of a guild following `strategy`.
+    ///
+    /// `complete` tells whether nothing found in the guild is final,
+    /// otherwise an HTTP request is made unless the strategy is `CacheOnly`.
+    async fn search<T>(
+        &self,
+        entity: &str,
+        guild_id: GuildId,
+        find: impl Fn(&Guild) -> Vec<T>,
+        complete: impl Fn(&Guild) -> bool,
+        strategy: ResolveStrategy,
+    ) -> Result<Vec<T>, ArgumentParseError> {
+        let cached = self.guilds.get(&guild_id).and_then(|guild| {
+            Some(find(guild)).filter(|found| !found.is_empty() || complete(guild))
+        });
+        let fetch = async {
+            self.http_requests.fetch_add(1, Ordering::SeqCst);
+            Ok(self.guilds.get(&guild_id).map(&find).unwrap_or_default())
+        };
+        resolve(strategy, entity, futures::future::ready(cached), fetch).await
+    }
+}
+
+#[async_trait]
+impl EntityResolver for MockResolver {
+    async fn user(&self, user_id: UserId, _: ResolveStrategy) -> Result<User, ArgumentParseError> {
+        self.users.get(&user_id).cloned().ok_or(ArgumentParseError::NotFound)
+    }
+
+    async fn member(
+        &self,
+        guild_id: GuildId,
+        user_id: UserId,
+        _: ResolveStrategy,
+    ) -> Result<Member, ArgumentParseError> {
+        let guild = self.get_guild(guild_id)?;
+        guild.members.get(&user_id).cloned().ok_or(ArgumentParseError::NotFound)
+    }
+
+    async fn channel(
+        &self,
+        channel_id: ChannelId,
+        _: ResolveStrategy,
+    ) -> Result<Channel, ArgumentParseError> {
+        self.channels.get(&channel_id).cloned().ok_or(ArgumentParseError::NotFound)
+    }
+
+    async fn role(
+        &self,
+        guild_id: GuildId,
+        role_id: RoleId,
+        _: ResolveStrategy,
+    ) -> Result<Role, ArgumentParseError> {
+        let guild = self.get_guild(guild_id)?;
+        guild.roles.get(&role_id).cloned().ok_or(ArgumentParseError::NotFound)
+    }
+
+    async fn guild(
+        &self,
+        guild_id: GuildId,
+        _: ResolveStrategy,
+    ) -> Result<Guild, ArgumentParseError> {
+        self.get_guild(guild_id).cloned()
+    }
+
+    async fn find_members(
+        &self,
+        guild_id: GuildId,
+        filter: Filter<'_, Member>,
+        strategy: ResolveStrategy,
+    ) -> Result<Vec<Member>, ArgumentParseError> {
+        let find = |guild: &Guild| guild.members.values().filter(|m| filter(m)).cloned().collect();
+        self.search("Member", guild_id, find, has_all_members, strategy).await
+    }
+
+    async fn find_channels(
+        &self,
+        guild_id: GuildId,
+        filter: Filter<'_, GuildChannel>,
+        strategy: ResolveStrategy,
+    ) -> Result<Vec<GuildChannel>, ArgumentParseError> {
+        let find = |guild: &Guild| guild.channels.values().filter(|c| filter(c)).cloned().collect();
+        self.search("Guild", guild_id, find, |_| true, strategy).await
+    }
+
+    async fn find_roles(
+        &self,
+        guild_id: GuildId,
+        filter: Filter<'_, Role>,
+        strategy: ResolveStrategy,
+    ) -> Result<Vec<Role>, ArgumentParseError> {
+        let find = |guild: &Guild| guild.roles.values().filter(|r| filter(r)).cloned().collect();
+        self.search("Guild", guild_id, find, |_| true, strategy).await
+    }
+}
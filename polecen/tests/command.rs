@@ -0,0 +1,104 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use polecen::arguments::resolve::ResolveStrategy;
+use polecen::command::{Command, CommandArguments, CommandArgumentsReadError, CommandError};
+use polecen::router::Router;
+use polecen::serenity::client::Context;
+use polecen::serenity::model::channel::Message;
+use polecen::serenity::model::guild::Member;
+use polecen::testing::Fixtures;
+
+static USER_INFO_TARGET: AtomicU64 = AtomicU64::new(0);
+
+#[polecen::command]
+async fn user_info(_ctx: &Context, _message: &Message, target: Member) {
+    USER_INFO_TARGET.store(target.user.id.0, Ordering::SeqCst);
+}
+
+fn fixtures() -> Fixtures {
+    Fixtures::new().member(2, "bob").author(2)
+}
+
+#[tokio::test]
+async fn default_name_is_kebab_case() {
+    assert_eq!(UserInfoArgs::help().name, "user-info");
+    assert_eq!(UserInfoArgs::usage(), "user-info <target>");
+
+    let fixtures = fixtures();
+    let ctx = fixtures.context().await;
+    let router = Router::new().prefix("!").command::<UserInfoCommand>();
+    assert!(router.dispatch(&ctx, &fixtures.message("!user-info bob")).await.unwrap());
+    assert_eq!(USER_INFO_TARGET.load(Ordering::SeqCst), 2);
+    assert!(!router.dispatch(&ctx, &fixtures.message("!user_info bob")).await.unwrap());
+}
+
+static ROLLS: Mutex<Vec<(u32, u32)>> = Mutex::new(Vec::new());
+static DICE_ERRORS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Roll dice.
+#[polecen::command(name = "roll", aliases = ["r", "dice"], args = DiceArgs, on_error = dice_error)]
+async fn roll_dice(
+    _ctx: &Context,
+    _message: &Message,
+    sides: u32,
+    #[polecen(named, short = 'n', default = 1)] count: u32,
+) -> Result<(), String> {
+    if sides == 0 {
+        return Err(String::from("dice need sides"));
+    }
+    ROLLS.lock().unwrap().push((sides, count));
+    Ok(())
+}
+
+async fn dice_error(
+    _ctx: &Context,
+    _message: &Message,
+    content: &str,
+    error: CommandArgumentsReadError,
+) -> Result<(), CommandError> {
+    DICE_ERRORS.lock().unwrap().push(format!("{}: {}", content, error.command()));
+    Ok(())
+}
+
+#[test]
+fn command_options() {
+    let help = DiceArgs::help();
+    assert_eq!((help.name, &help.aliases[..]), ("roll", &["r", "dice"][..]));
+    assert_eq!(help.description, Some("Roll dice."));
+    assert_eq!(DiceArgs::usage(), "roll <sides> [--count <count>]");
+}
+
+#[tokio::test]
+async fn invoke_command() {
+    let fixtures = fixtures();
+    let ctx = fixtures.context().await;
+    let message = fixtures.message("!roll 20 -n 2");
+    RollDiceCommand::invoke(&ctx, &message, "20 -n 2", ResolveStrategy::default()).await.unwrap();
+    assert_eq!(*ROLLS.lock().unwrap(), [(20, 2)]);
+
+    // errors returned by the handler are converted to command errors
+    let error =
+        RollDiceCommand::invoke(&ctx, &message, "0", ResolveStrategy::default()).await.unwrap_err();
+    assert_eq!(error.to_string(), "dice need sides");
+
+    // arguments which can't be read go to on_error
+    RollDiceCommand::invoke(&ctx, &message, "many", ResolveStrategy::default()).await.unwrap();
+    assert_eq!(*DICE_ERRORS.lock().unwrap(), ["many: roll"]);
+    assert_eq!(ROLLS.lock().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn read_errors_are_returned_by_default() {
+    let fixtures = fixtures();
+    let ctx = fixtures.context().await;
+    let message = fixtures.message("!user-info");
+    let error =
+        UserInfoCommand::invoke(&ctx, &message, "", ResolveStrategy::default()).await.unwrap_err();
+    match error.downcast_ref::<CommandArgumentsReadError>() {
+        Some(CommandArgumentsReadError::RequiredArgumentMissing { name, .. }) => {
+            assert_eq!(name, "target")
+        },
+        _ => panic!("expected a missing argument, got {:?}", error),
+    }
+}
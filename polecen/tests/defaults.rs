@@ -0,0 +1,68 @@
+use std::time::Duration;
+
+use polecen::command::{CommandArguments, CommandArgumentsReadError};
+use polecen::testing::{parse, Fixtures};
+
+const MAX_COUNT: u32 = 100;
+
+polecen::expand_command_here!((RemindArgs) remind => {
+    message: String;
+    wait: Duration = Duration::from_secs(60);
+    count: u32 = MAX_COUNT / 4;
+    --channel | -c: String = String::from("general");
+});
+
+async fn remind(content: &str) -> Result<RemindArgs, CommandArgumentsReadError> {
+    parse::<RemindArgs>(content, &Fixtures::new()).await
+}
+
+#[tokio::test]
+async fn defaults_when_not_given() {
+    let args = remind("hello").await.unwrap();
+    assert_eq!(args.message, "hello");
+    assert_eq!(
+        (args.wait, args.count, args.channel.as_str()),
+        (Duration::from_secs(60), 25, "general")
+    );
+}
+
+#[tokio::test]
+async fn given_values_replace_defaults() {
+    let args = remind("hello 5m 2 -c bots").await.unwrap();
+    assert_eq!(
+        (args.wait, args.count, args.channel.as_str()),
+        (Duration::from_secs(300), 2, "bots")
+    );
+
+    let args = remind("hello 1h").await.unwrap();
+    assert_eq!((args.wait, args.count), (Duration::from_secs(3600), 25));
+}
+
+#[tokio::test]
+async fn given_values_are_still_parsed() {
+    match remind("hello soon").await.unwrap_err() {
+        CommandArgumentsReadError::ValueParseError { name, value, .. } => {
+            assert_eq!((name.as_str(), value.as_str()), ("wait", "soon"));
+        },
+        err => panic!("expected a parse error, got {:?}", err),
+    }
+}
+
+#[test]
+fn defaults_in_help() {
+    let help = RemindArgs::help();
+    // spacing depends on whether the toolchain gives the source text of the expression
+    let defaults = help
+        .arguments
+        .iter()
+        .map(|argument| argument.default.map(|default| default.replace(' ', "")))
+        .collect::<Vec<_>>();
+    assert_eq!(defaults, [
+        None,
+        Some("Duration::from_secs(60)".to_owned()),
+        Some("MAX_COUNT/4".to_owned()),
+        Some("String::from(\"general\")".to_owned())
+    ]);
+    assert!(help.arguments[1..].iter().all(|argument| !argument.required));
+    assert_eq!(RemindArgs::usage(), "remind <message> [wait] [count] [--channel <channel>]");
+}
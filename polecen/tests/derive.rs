@@ -0,0 +1,103 @@
+use std::sync::{Arc, Mutex};
+
+use polecen::arguments::prelude::*;
+use polecen::command::CommandError;
+use polecen::router::Router;
+use polecen::serenity::client::Context;
+use polecen::serenity::model::channel::Message;
+use polecen::serenity::model::guild::Member;
+use polecen::testing::{parse, Fixtures};
+
+/// Moderation commands.
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, polecen::CommandArguments)]
+#[polecen(name = "mod", ignore_case)]
+pub enum ModArgs {
+    /// Kick a member.
+    Kick(KickArgs),
+    #[polecen(alias = "ver")]
+    Version,
+}
+
+#[derive(Debug, polecen::CommandArguments)]
+#[polecen(name = "mod kick")]
+pub struct KickArgs {
+    /// Member to kick.
+    target: Member,
+    #[polecen(named, short = 'd', default = 0)]
+    delete_days: u32,
+    #[polecen(span = ..)]
+    reason: Option<String>,
+}
+
+fn fixtures() -> Fixtures {
+    Fixtures::new().member(2, "bob").member_with_nick(3, "carol", Some("Caz")).author(2)
+}
+
+#[tokio::test]
+async fn derived_struct_arguments() {
+    match parse::<ModArgs>("kick caz -d 2 spam, again", &fixtures()).await.unwrap() {
+        ModArgs::Kick(args) => {
+            assert_eq!(args.target.user.id.0, 3);
+            assert_eq!(args.delete_days, 2);
+            assert_eq!(args.reason.as_deref(), Some("spam, again"));
+        },
+        args => panic!("expected kick, got {:?}", args),
+    }
+    match parse::<ModArgs>("kick bob", &fixtures()).await.unwrap() {
+        ModArgs::Kick(args) => assert_eq!((args.delete_days, args.reason), (0, None)),
+        args => panic!("expected kick, got {:?}", args),
+    }
+}
+
+#[tokio::test]
+async fn derived_enum_subcommands() {
+    assert!(matches!(parse::<ModArgs>("VER", &fixtures()).await.unwrap(), ModArgs::Version));
+    assert!(matches!(parse::<ModArgs>("version", &fixtures()).await.unwrap(), ModArgs::Version));
+    match parse::<ModArgs>("kik", &fixtures()).await.unwrap_err() {
+        CommandArgumentsReadError::UnknownSubcommand { command, suggestions, .. } => {
+            assert_eq!((command.as_str(), suggestions), ("mod", vec!["kick".to_owned()]));
+        },
+        error => panic!("expected an unknown subcommand, got {:?}", error),
+    }
+}
+
+#[tokio::test]
+async fn derived_help() {
+    let help = ModArgs::help();
+    assert_eq!(help.description, Some("Moderation commands."));
+    assert_eq!(help.subcommands[0].description, Some("Kick a member."));
+    assert_eq!(help.subcommands[1].aliases, ["ver"]);
+}
+
+#[derive(Default)]
+struct Recorder {
+    calls: Mutex<Vec<String>>,
+}
+
+#[polecen::async_trait]
+impl ModArgsHandler for Recorder {
+    async fn kick(&self, _: &Context, _: &Message, args: KickArgs) -> Result<(), CommandError> {
+        self.calls.lock().unwrap().push(format!("kick {}", args.target.user.id));
+        Ok(())
+    }
+
+    async fn version(&self, _: &Context, _: &Message) -> Result<(), CommandError> {
+        self.calls.lock().unwrap().push("version".to_owned());
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn derived_handler() {
+    let fixtures = fixtures();
+    let ctx = fixtures.context().await;
+    let recorder = Arc::new(Recorder::default());
+    let router = Router::new().prefix("!").group::<ModArgs>(recorder.clone());
+
+    assert!(router.dispatch(&ctx, &fixtures.message("!mod kick caz")).await.unwrap());
+    assert!(router.dispatch(&ctx, &fixtures.message("!mod ver")).await.unwrap());
+    let message = fixtures.message("mod ver");
+    ModArgs::dispatch(&*recorder, &ctx, &message, "ver", ResolveStrategy::CacheOnly).await.unwrap();
+    assert_eq!(*recorder.calls.lock().unwrap(), ["kick 3", "version", "version"]);
+}
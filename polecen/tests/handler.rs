@@ -0,0 +1,134 @@
+use std::sync::Mutex;
+
+use polecen::arguments::resolve::ResolveStrategy;
+use polecen::command::{CommandArgumentsReadError, CommandError};
+use polecen::serenity::client::Context;
+use polecen::serenity::model::channel::Message;
+use polecen::serenity::model::guild::{Member, Role};
+use polecen::testing::{parse, Fixtures};
+
+polecen::expand_command_here!((AdminArgs) admin => match {
+    kick => {
+        target: Member;
+        reason: Option<String> [..];
+    },
+    roles => match {
+        add => {
+            role: Role;
+        },
+    },
+    ping => {},
+});
+
+#[derive(Default)]
+struct Admin {
+    calls: Mutex<Vec<String>>,
+}
+
+impl Admin {
+    fn record(&self, call: String) {
+        self.calls.lock().unwrap().push(call);
+    }
+}
+
+#[polecen::async_trait]
+impl AdminArgsHandler for Admin {
+    async fn kick(
+        &self,
+        _: &Context,
+        _: &Message,
+        args: AdminArgsKick,
+    ) -> Result<(), CommandError> {
+        self.record(format!("kick {} {:?}", args.target.user.id, args.reason));
+        Ok(())
+    }
+
+    async fn roles(
+        &self,
+        ctx: &Context,
+        message: &Message,
+        args: AdminArgsRoles,
+    ) -> Result<(), CommandError> {
+        args.route(self, ctx, message).await
+    }
+
+    async fn ping(&self, _: &Context, _: &Message) -> Result<(), CommandError> {
+        Err("pong".into())
+    }
+
+    async fn on_error(
+        &self,
+        _: &Context,
+        _: &Message,
+        content: &str,
+        error: CommandArgumentsReadError,
+    ) -> Result<(), CommandError> {
+        self.record(format!("{}: {}", content, error.command()));
+        Ok(())
+    }
+}
+
+#[polecen::async_trait]
+impl AdminArgsRolesHandler for Admin {
+    async fn add(
+        &self,
+        _: &Context,
+        _: &Message,
+        args: AdminArgsRolesAdd,
+    ) -> Result<(), CommandError> {
+        self.record(format!("add {}", args.role.name));
+        Ok(())
+    }
+}
+
+fn fixtures() -> Fixtures {
+    Fixtures::new().member(2, "bob").role(10, "Mods").author(2)
+}
+
+async fn dispatch(admin: &Admin, content: &str) -> Result<(), CommandError> {
+    let fixtures = fixtures();
+    let ctx = fixtures.context().await;
+    let message = fixtures.message(&format!("!admin {}", content));
+    AdminArgs::dispatch(admin, &ctx, &message, content, ResolveStrategy::CacheOnly).await
+}
+
+#[tokio::test]
+async fn route_arguments() {
+    let fixtures = fixtures();
+    let ctx = fixtures.context().await;
+    let message = fixtures.message("!admin kick bob spam");
+    let admin = Admin::default();
+    let args = parse::<AdminArgs>("kick bob spam", &fixtures).await.unwrap();
+    args.route(&admin, &ctx, &message).await.unwrap();
+    assert_eq!(*admin.calls.lock().unwrap(), ["kick 2 Some(\"spam\")"]);
+}
+
+#[tokio::test]
+async fn dispatch_subcommands() {
+    let admin = Admin::default();
+    dispatch(&admin, "kick bob").await.unwrap();
+    dispatch(&admin, "roles add Mods").await.unwrap();
+    assert_eq!(dispatch(&admin, "ping").await.unwrap_err().to_string(), "pong");
+    assert_eq!(*admin.calls.lock().unwrap(), ["kick 2 None", "add Mods"]);
+}
+
+#[tokio::test]
+async fn read_errors_go_to_on_error() {
+    let admin = Admin::default();
+    dispatch(&admin, "kick nobody").await.unwrap();
+    dispatch(&admin, "roles add Admins").await.unwrap();
+    assert_eq!(*admin.calls.lock().unwrap(), [
+        "kick nobody: admin kick",
+        "roles add Admins: admin roles add"
+    ]);
+
+    // the default on_error returns the error
+    let fixtures = fixtures();
+    let ctx = fixtures.context().await;
+    let message = fixtures.message("!admin roles add Admins");
+    let error =
+        AdminArgsRoles::dispatch(&admin, &ctx, &message, "add Admins", ResolveStrategy::CacheOnly)
+            .await
+            .unwrap_err();
+    assert!(error.downcast_ref::<CommandArgumentsReadError>().is_some());
+}
@@ -0,0 +1,68 @@
+use polecen::arguments::prelude::*;
+use polecen::serenity::model::guild::Member;
+
+polecen::expand_command_here!((TestCommandArgs) test, "Test commands" => match {
+    kick | k, "Kick a member" => {
+        target: Member, "Member to kick";
+        reason: Option<String> [..];
+    },
+    roll => {
+        dice: Vec<u32> [1..=5];
+    },
+    version | ver | "?" => {},
+});
+
+#[test]
+fn usage() {
+    assert_eq!(TestCommandArgs::usage(), "test <kick|roll|version>");
+    assert_eq!(TestCommandArgsKick::usage(), "test kick <target> [reason...]");
+    assert_eq!(TestCommandArgsRoll::usage(), "test roll <dice...>");
+    assert_eq!(TestCommandArgs::help().subcommands[2].usage, "test version");
+}
+
+#[test]
+fn subcommands_help() {
+    let help = TestCommandArgs::help();
+    assert_eq!((help.name, help.description), ("test", Some("Test commands")));
+    let kick = &help.subcommands[0];
+    assert_eq!((kick.name, &kick.aliases[..]), ("kick", &["k"][..]));
+    assert_eq!(kick.usage, TestCommandArgsKick::usage());
+    assert_eq!(help.subcommands[2].aliases, ["ver", "?"]);
+
+    assert_eq!(
+        help.to_string(),
+        "Usage: test <kick|roll|version>\n\
+         Test commands\n\
+         \n\
+         Subcommands:\n  \
+           kick (k) - Kick a member\n  \
+           roll\n  \
+           version (ver, ?)\n"
+    );
+}
+
+#[test]
+fn arguments_help() {
+    let help = TestCommandArgsKick::help();
+    let target = &help.arguments[0];
+    assert_eq!(
+        (target.name, target.type_name.as_str(), target.required),
+        ("target", "member", true)
+    );
+    assert!(!help.arguments[1].required);
+
+    assert_eq!(
+        help.to_string(),
+        "Usage: test kick <target> [reason...]\n\
+         Kick a member\n\
+         Aliases: k\n\
+         \n\
+         Arguments:\n  \
+           target (member) - Member to kick\n  \
+           reason (text, optional)\n"
+    );
+    assert_eq!(
+        TestCommandArgsRoll::help().to_string(),
+        "Usage: test roll <dice...>\n\nArguments:\n  dice (integer)\n"
+    );
+}
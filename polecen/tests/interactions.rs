@@ -0,0 +1,203 @@
+use polecen::arguments::parse::{ArgumentParseContext, ArgumentParseError};
+use polecen::command::CommandArgumentsReadError;
+use polecen::interactions::{
+    ApplicationCommandInteractionDataOption,
+    ApplicationCommandInteractionDataOptionValue,
+    InteractionArguments,
+};
+use polecen::serde_json::{self, json, Value};
+use polecen::serenity::model::guild::{Member, Role};
+use polecen::testing::Fixtures;
+
+polecen::expand_command_here!((ModArgs) moderation, "Moderation commands" => match {
+    ban, "Ban a member" => {
+        reason: Option<String>;
+        target: Member, "Member to ban";
+        days: u32 = 0;
+        --silent | -s: bool;
+    },
+    roles => match {
+        add => {
+            role: Role;
+            member: Member = @author;
+        },
+    },
+    version => {},
+});
+
+#[test]
+fn application_command() {
+    assert_eq!(
+        ModArgs::application_command(),
+        json!({
+            "name": "moderation",
+            "description": "Moderation commands",
+            "options": [
+                {
+                    "type": 1,
+                    "name": "ban",
+                    "description": "Ban a member",
+                    "options": [
+                        { "type": 6, "name": "target", "description": "Member to ban", "required": true },
+                        { "type": 3, "name": "reason", "description": "reason", "required": false },
+                        { "type": 4, "name": "days", "description": "days", "required": false },
+                        { "type": 5, "name": "silent", "description": "silent", "required": false },
+                    ],
+                },
+                {
+                    "type": 2,
+                    "name": "roles",
+                    "description": "roles",
+                    "options": [{
+                        "type": 1,
+                        "name": "add",
+                        "description": "add",
+                        "options": [
+                            { "type": 8, "name": "role", "description": "role", "required": true },
+                            { "type": 6, "name": "member", "description": "member", "required": false },
+                        ],
+                    }],
+                },
+                { "type": 1, "name": "version", "description": "version", "options": [] },
+            ],
+        })
+    );
+}
+
+fn fixtures() -> Fixtures {
+    Fixtures::new().member(2, "bob").member(3, "carol").role(10, "Mods").author(3)
+}
+
+fn options(options: Value) -> Vec<ApplicationCommandInteractionDataOption> {
+    serde_json::from_value(options).unwrap()
+}
+
+async fn read(
+    options: &[ApplicationCommandInteractionDataOption],
+) -> Result<ModArgs, CommandArgumentsReadError> {
+    let fixtures = fixtures();
+    let resolver = fixtures.resolver();
+    let mut ctx = ArgumentParseContext::with_resolver(&resolver, fixtures.guild_id);
+    ctx.author_id = fixtures.author_id;
+    ModArgs::read_interaction(options, 0, ctx).await
+}
+
+#[tokio::test]
+async fn read_options() {
+    let options = options(json!([{
+        "type": 1,
+        "name": "ban",
+        "options": [
+            { "type": 4, "name": "days", "value": 7 },
+            { "type": 6, "name": "target", "value": "2" },
+            { "type": 5, "name": "silent", "value": true },
+        ],
+    }]));
+    match read(&options).await.unwrap() {
+        ModArgs::Ban(args) => {
+            assert_eq!(args.target.user.id, 2);
+            assert_eq!((args.reason, args.days, args.silent), (None, 7, true));
+        },
+        args => panic!("expected ban, got {:?}", args),
+    }
+}
+
+#[tokio::test]
+async fn read_subcommand_group() {
+    let options = options(json!([{
+        "type": 2,
+        "name": "roles",
+        "options": [{
+            "type": 1,
+            "name": "add",
+            "options": [{ "type": 8, "name": "role", "value": "10" }],
+        }],
+    }]));
+    match read(&options).await.unwrap() {
+        ModArgs::Roles(ModArgsRoles::Add(args)) => {
+            assert_eq!(args.role.name, "Mods");
+            assert_eq!(args.member.user.id, 3);
+        },
+        args => panic!("expected roles add, got {:?}", args),
+    }
+}
+
+#[tokio::test]
+async fn resolved_values_are_used() {
+    let mut options = options(json!([{
+        "type": 2,
+        "name": "roles",
+        "options": [{
+            "type": 1,
+            "name": "add",
+            "options": [{ "type": 8, "name": "role", "value": "11" }],
+        }],
+    }]));
+    let role = serde_json::from_value(json!({
+        "id": "11",
+        "guild_id": Fixtures::GUILD_ID,
+        "name": "Helpers",
+        "color": 0,
+        "hoist": false,
+        "managed": false,
+        "mentionable": true,
+        "permissions": "0",
+        "position": 2,
+    }))
+    .unwrap();
+    options[0].options[0].options[0].resolved =
+        Some(ApplicationCommandInteractionDataOptionValue::Role(role));
+    match read(&options).await.unwrap() {
+        ModArgs::Roles(ModArgsRoles::Add(args)) => assert_eq!(args.role.name, "Helpers"),
+        args => panic!("expected roles add, got {:?}", args),
+    }
+}
+
+#[tokio::test]
+async fn missing_option() {
+    let options = options(json!([{
+        "type": 1,
+        "name": "ban",
+        "options": [{ "type": 3, "name": "reason", "value": "spam" }],
+    }]));
+    match read(&options).await.unwrap_err() {
+        CommandArgumentsReadError::RequiredArgumentMissing { position, command, name } => {
+            assert_eq!(
+                (position, command.as_str(), name.as_str()),
+                (2, "moderation ban", "target")
+            );
+        },
+        err => panic!("expected a missing argument, got {:?}", err),
+    }
+}
+
+#[tokio::test]
+async fn invalid_option() {
+    let options = options(json!([{
+        "type": 1,
+        "name": "ban",
+        "options": [{ "type": 6, "name": "target", "value": "4" }],
+    }]));
+    match read(&options).await.unwrap_err() {
+        CommandArgumentsReadError::ValueParseError { name, value, inner, .. } => {
+            assert_eq!((name.as_str(), value.as_str()), ("target", "4"));
+            assert_eq!(inner, ArgumentParseError::NotFound);
+        },
+        err => panic!("expected a parse error, got {:?}", err),
+    }
+}
+
+#[tokio::test]
+async fn unknown_subcommand() {
+    let options = options(json!([{ "type": 1, "name": "kick", "options": [] }]));
+    match read(&options).await.unwrap_err() {
+        CommandArgumentsReadError::UnknownSubcommand { given, .. } => assert_eq!(given, "kick"),
+        err => panic!("expected an unknown subcommand, got {:?}", err),
+    }
+    match read(&[]).await.unwrap_err() {
+        CommandArgumentsReadError::MissingSubcommand { command, .. } => {
+            assert_eq!(command, "moderation")
+        },
+        err => panic!("expected a missing subcommand, got {:?}", err),
+    }
+}
@@ -0,0 +1,81 @@
+use polecen::command::CommandArgumentsReadError;
+use polecen::testing::{parse, Fixtures};
+
+polecen::expand_command_here!((SearchArgs) search => {
+    query: String;
+    --exact | -e: bool;
+    --limit | -l: u32 = 10;
+    --sort_by: Option<String>;
+    rest: Option<String> [..];
+});
+
+async fn search(content: &str) -> Result<SearchArgs, CommandArgumentsReadError> {
+    parse::<SearchArgs>(content, &Fixtures::new()).await
+}
+
+#[tokio::test]
+async fn named_arguments_are_optional() {
+    let args = search("cats").await.unwrap();
+    assert_eq!(args.query, "cats");
+    assert_eq!((args.exact, args.limit, args.sort_by, args.rest), (false, 10, None, None));
+}
+
+#[tokio::test]
+async fn named_argument_forms() {
+    for content in &["--limit 5 cats", "--limit=5 cats", "limit=5 cats", "-l 5 cats", "-l=5 cats"] {
+        let args = search(content).await.unwrap();
+        assert_eq!((args.query.as_str(), args.limit), ("cats", 5), "{}", content);
+    }
+    let args = search("cats --sort-by date").await.unwrap();
+    assert_eq!(args.sort_by.as_deref(), Some("date"));
+}
+
+#[tokio::test]
+async fn named_arguments_anywhere() {
+    let args = search("-e cats and -l 3 dogs --exact").await.unwrap();
+    assert!(args.exact);
+    assert_eq!(args.limit, 3);
+    assert_eq!((args.query.as_str(), args.rest.as_deref()), ("cats", Some("and dogs")));
+}
+
+#[tokio::test]
+async fn end_of_named_arguments() {
+    let args = search("-e -- --limit 3").await.unwrap();
+    assert!(args.exact);
+    assert_eq!(args.limit, 10);
+    assert_eq!((args.query.as_str(), args.rest.as_deref()), ("--limit", Some("3")));
+}
+
+#[tokio::test]
+async fn unknown_option() {
+    match search("cats --fast").await.unwrap_err() {
+        CommandArgumentsReadError::UnknownOption { position, span, command, given } => {
+            assert_eq!((position, span, command.as_str()), (1, Some(5..11), "search"));
+            assert_eq!(given, "--fast");
+        },
+        err => panic!("expected an unknown option, got {:?}", err),
+    }
+}
+
+#[tokio::test]
+async fn missing_option_value() {
+    match search("cats --limit").await.unwrap_err() {
+        CommandArgumentsReadError::RequiredArgumentMissing { position, name, .. } => {
+            assert_eq!((position, name.as_str()), (2, "limit"));
+        },
+        err => panic!("expected a missing value, got {:?}", err),
+    }
+}
+
+#[tokio::test]
+async fn invalid_option_value() {
+    match search("cats -l=many").await.unwrap_err() {
+        CommandArgumentsReadError::ValueParseError { position, span, name, value, .. } => {
+            assert_eq!(
+                (position, span, name.as_str(), value.as_str()),
+                (1, Some(8..12), "limit", "many")
+            );
+        },
+        err => panic!("expected a parse error, got {:?}", err),
+    }
+}
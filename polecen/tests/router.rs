@@ -0,0 +1,149 @@
+use std::sync::{Arc, Mutex};
+
+use polecen::command::CommandError;
+use polecen::router::Router;
+use polecen::serenity::client::Context;
+use polecen::serenity::model::channel::Message;
+use polecen::testing::Fixtures;
+
+polecen::expand_command_here!((ToolsArgs) tools | t => match {
+    echo => {
+        text: String [..];
+    },
+    fail => {},
+});
+
+#[derive(Default)]
+struct Tools {
+    echoed: Mutex<Vec<String>>,
+}
+
+#[polecen::async_trait]
+impl ToolsArgsHandler for Tools {
+    async fn echo(
+        &self,
+        _: &Context,
+        _: &Message,
+        args: ToolsArgsEcho,
+    ) -> Result<(), CommandError> {
+        self.echoed.lock().unwrap().push(args.text);
+        Ok(())
+    }
+
+    async fn fail(&self, _: &Context, _: &Message) -> Result<(), CommandError> {
+        Err("failed".into())
+    }
+}
+
+#[polecen::command(aliases = ["p"])]
+async fn ping(_ctx: &Context, _message: &Message) {}
+
+#[polecen::command(aliases = ["p"])]
+async fn pong(_ctx: &Context, _message: &Message) {}
+
+fn fixtures() -> Fixtures {
+    Fixtures::new().member(2, "bob").author(2)
+}
+
+async fn dispatch(router: &Router, fixtures: &Fixtures, content: &str) -> bool {
+    let ctx = fixtures.context().await;
+    router.dispatch(&ctx, &fixtures.message(content)).await.unwrap()
+}
+
+fn router(tools: &Arc<Tools>) -> Router {
+    Router::new()
+        .prefix("!")
+        .prefix("bot ")
+        .command::<PingCommand>()
+        .group::<ToolsArgs>(tools.clone())
+}
+
+#[tokio::test]
+async fn prefixes() {
+    let tools = Arc::new(Tools::default());
+    let router = router(&tools);
+    let fixtures = fixtures();
+    assert!(dispatch(&router, &fixtures, "!tools echo a").await);
+    assert!(dispatch(&router, &fixtures, "!  t echo b").await);
+    assert!(dispatch(&router, &fixtures, "bot tools echo c").await);
+    assert!(dispatch(&router, &fixtures, "!p").await);
+    assert_eq!(*tools.echoed.lock().unwrap(), ["a", "b", "c"]);
+
+    assert!(!dispatch(&router, &fixtures, "tools echo d").await);
+    assert!(!dispatch(&router, &fixtures, "?tools echo d").await);
+    assert!(!dispatch(&router, &fixtures, "!unknown").await);
+    assert!(!dispatch(&router, &fixtures, "!TOOLS echo d").await);
+    assert_eq!(tools.echoed.lock().unwrap().len(), 3);
+}
+
+#[tokio::test]
+async fn dynamic_prefix() {
+    let tools = Arc::new(Tools::default());
+    let router = router(&tools).dynamic_prefix(|_, message| {
+        let prefix = message.guild_id.map(|_| String::from("?"));
+        Box::pin(async move { prefix })
+    });
+    assert!(dispatch(&router, &fixtures(), "?tools echo guild").await);
+    assert!(!dispatch(&router, &fixtures(), "!tools echo guild").await);
+    // static prefixes are used when there is no dynamic prefix
+    assert!(dispatch(&router, &fixtures().dm(), "!tools echo dm").await);
+    assert_eq!(*tools.echoed.lock().unwrap(), ["guild", "dm"]);
+}
+
+#[tokio::test]
+async fn mention_prefix() {
+    let tools = Arc::new(Tools::default());
+    let fixtures = fixtures();
+    let bot = Fixtures::BOT_ID.0;
+    assert!(!dispatch(&router(&tools), &fixtures, &format!("<@{}> ping", bot)).await);
+
+    let router = router(&tools).mention(true);
+    assert!(dispatch(&router, &fixtures, &format!("<@{}> tools echo a", bot)).await);
+    assert!(dispatch(&router, &fixtures, &format!("<@!{}>tools echo b", bot)).await);
+    assert!(!dispatch(&router, &fixtures, "<@2> tools echo c").await);
+    assert!(dispatch(&router, &fixtures, "!tools echo d").await);
+    assert_eq!(*tools.echoed.lock().unwrap(), ["a", "b", "d"]);
+}
+
+#[tokio::test]
+async fn ignore_bots() {
+    let tools = Arc::new(Tools::default());
+    let fixtures = fixtures();
+    let ctx = fixtures.context().await;
+    let mut message = fixtures.message("!ping");
+    message.author.bot = true;
+    assert!(!router(&tools).dispatch(&ctx, &message).await.unwrap());
+    assert!(router(&tools).ignore_bots(false).dispatch(&ctx, &message).await.unwrap());
+}
+
+#[tokio::test]
+async fn ignore_case() {
+    let tools = Arc::new(Tools::default());
+    let router = router(&tools).ignore_case(true);
+    assert!(dispatch(&router, &fixtures(), "!PING").await);
+    assert!(dispatch(&router, &fixtures(), "!Tools echo a").await);
+}
+
+#[tokio::test]
+async fn command_errors() {
+    let tools = Arc::new(Tools::default());
+    let fixtures = fixtures();
+    let ctx = fixtures.context().await;
+    let router = router(&tools);
+    let error = router.dispatch(&ctx, &fixtures.message("!tools fail")).await.unwrap_err();
+    assert_eq!(error.to_string(), "failed");
+    assert!(router.dispatch(&ctx, &fixtures.message("!tools")).await.is_err());
+}
+
+#[test]
+fn registered_commands() {
+    let router = router(&Arc::new(Tools::default()));
+    let names = router.commands().map(|help| help.name).collect::<Vec<_>>();
+    assert_eq!(names, ["ping", "tools"]);
+}
+
+#[test]
+#[should_panic(expected = "`p` is already registered by `ping`")]
+fn duplicate_names() {
+    let _ = Router::new().command::<PingCommand>().command::<PongCommand>();
+}
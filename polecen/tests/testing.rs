@@ -0,0 +1,381 @@
+use polecen::arguments::default::{Comma, List, Pipe};
+use polecen::arguments::prelude::*;
+use polecen::serenity::model::channel::GuildChannel;
+use polecen::serenity::model::guild::{Member, Role};
+use polecen::testing::{parse, Fixtures, MockResolver};
+
+polecen::expand_command_here!((TestCommandArgs) test => match {
+    kick => {
+        target: Member;
+        reason: Option<String> [..];
+    },
+    promote => {
+        role: Role;
+    },
+    topic => {
+        channel: GuildChannel;
+    },
+    whoami => {
+        who: Member = @author;
+    },
+    notify => {
+        members: List<Member, Comma>;
+        roles: Option<List<Role, Pipe>>;
+    },
+    masskick => {
+        targets: Vec<Member> [2..=3];
+    },
+    ban => {
+        target: Member;
+        reason: Option<String> [..];
+        --silent | -s: bool;
+        --delete_days | -d: u32 = 0;
+    },
+});
+
+fn fixtures() -> Fixtures {
+    Fixtures::new()
+        .member(2, "bob")
+        .member_with_nick(3, "carol", Some("Caz"))
+        .member(4, "sam")
+        .member(5, "sam")
+        .role(10, "Mods")
+        .channel(20, "general")
+        .author(2)
+}
+
+async fn kick(content: &str, fixtures: &Fixtures) -> TestCommandArgsKick {
+    match parse::<TestCommandArgs>(content, fixtures).await.unwrap() {
+        TestCommandArgs::Kick(args) => args,
+        args => panic!("expected kick, got {:?}", args),
+    }
+}
+
+async fn ban(content: &str, fixtures: &Fixtures) -> TestCommandArgsBan {
+    match parse::<TestCommandArgs>(content, fixtures).await.unwrap() {
+        TestCommandArgs::Ban(args) => args,
+        args => panic!("expected ban, got {:?}", args),
+    }
+}
+
+async fn value_error(content: &str, fixtures: &Fixtures) -> ArgumentParseError {
+    match parse::<TestCommandArgs>(content, fixtures).await.unwrap_err() {
+        CommandArgumentsReadError::ValueParseError { inner, .. } => inner,
+        error => panic!("expected a value error, got {:?}", error),
+    }
+}
+
+#[test]
+fn fixtures_build_a_resolver() {
+    let resolver = fixtures().user(30, "outsider").resolver();
+    let guild = &resolver.guilds[&Fixtures::GUILD_ID];
+    assert_eq!(guild.members.len(), 4);
+    assert_eq!(guild.roles.len(), 1);
+    assert_eq!(guild.channels.len(), 1);
+    // members and the user who isn't a member
+    assert_eq!(resolver.users.len(), 5);
+}
+
+#[tokio::test]
+async fn member_by_id() {
+    let args = kick("kick 2", &fixtures()).await;
+    assert_eq!(args.target.user.id.0, 2);
+    assert_eq!(args.reason, None);
+}
+
+#[tokio::test]
+async fn member_by_mention() {
+    assert_eq!(kick("kick <@2>", &fixtures()).await.target.user.id.0, 2);
+    assert_eq!(kick("kick <@!3>", &fixtures()).await.target.user.id.0, 3);
+}
+
+#[tokio::test]
+async fn member_by_name() {
+    let fixtures = fixtures();
+    assert_eq!(kick("kick bob", &fixtures).await.target.user.id.0, 2);
+    assert_eq!(kick("kick BOB", &fixtures).await.target.user.id.0, 2);
+    assert_eq!(kick("kick caz", &fixtures).await.target.user.id.0, 3);
+    assert_eq!(kick("kick carol#0003", &fixtures).await.target.user.id.0, 3);
+}
+
+#[tokio::test]
+async fn ambiguous_member() {
+    match value_error("kick sam", &fixtures()).await {
+        ArgumentParseError::Ambiguous(candidates) => {
+            assert_eq!(candidates.len(), 2);
+            assert!(candidates.contains(&"sam#0004".to_owned()));
+            assert!(candidates.contains(&"sam#0005".to_owned()));
+        },
+        error => panic!("expected an ambiguous value, got {:?}", error),
+    }
+}
+
+#[tokio::test]
+async fn unknown_member() {
+    assert_eq!(value_error("kick nobody", &fixtures()).await, ArgumentParseError::NotFound);
+    assert_eq!(value_error("kick 99", &fixtures()).await, ArgumentParseError::NotFound);
+}
+
+#[tokio::test]
+async fn unexpected_mention() {
+    assert_eq!(
+        value_error("kick <@&10>", &fixtures()).await,
+        ArgumentParseError::UnexpectedMention { expected: "user", given: "role" }
+    );
+}
+
+#[tokio::test]
+async fn reason_keeps_typed_text() {
+    let fixtures = fixtures();
+    let args = kick("kick bob it's 'cause he's rude", &fixtures).await;
+    assert_eq!(args.reason.as_deref(), Some("it's 'cause he's rude"));
+    let args = kick("kick bob he said \"stop now\"", &fixtures).await;
+    assert_eq!(args.reason.as_deref(), Some("he said \"stop now\""));
+    let args = kick("kick bob \"quoted reason\"", &fixtures).await;
+    assert_eq!(args.reason.as_deref(), Some("quoted reason"));
+}
+
+#[tokio::test]
+async fn reason_without_named_arguments() {
+    let fixtures = fixtures();
+    let args = ban("ban bob spam --silent more  \"and\" -d 4 end", &fixtures).await;
+    assert!(args.silent);
+    assert_eq!(args.delete_days, 4);
+    assert_eq!(args.reason.as_deref(), Some("spam more  \"and\" end"));
+
+    let args = ban("ban bob spam, really -- --silent", &fixtures).await;
+    assert!(!args.silent);
+    assert_eq!(args.reason.as_deref(), Some("spam, really --silent"));
+}
+
+#[test]
+fn named_arguments_usage() {
+    assert_eq!(
+        TestCommandArgsBan::usage(),
+        "test ban <target> [reason...] [--silent] [--delete-days <delete-days>]"
+    );
+}
+
+#[tokio::test]
+async fn missing_argument() {
+    match parse::<TestCommandArgs>("kick", &fixtures()).await.unwrap_err() {
+        CommandArgumentsReadError::RequiredArgumentMissing { command, name, .. } => {
+            assert_eq!(command, "test kick");
+            assert_eq!(name, "target");
+        },
+        error => panic!("expected a missing argument, got {:?}", error),
+    }
+}
+
+#[tokio::test]
+async fn missing_subcommand() {
+    let error = parse::<TestCommandArgs>("", &fixtures()).await.unwrap_err();
+    assert!(matches!(error, CommandArgumentsReadError::MissingSubcommand { .. }));
+}
+
+#[tokio::test]
+async fn role_by_mention_and_name() {
+    for content in &["promote <@&10>", "promote 10", "promote mods"] {
+        match parse::<TestCommandArgs>(content, &fixtures()).await.unwrap() {
+            TestCommandArgs::Promote(args) => assert_eq!(args.role.id.0, 10),
+            args => panic!("expected promote, got {:?}", args),
+        }
+    }
+}
+
+#[tokio::test]
+async fn channel_by_mention_and_name() {
+    for content in &["topic <#20>", "topic #general", "topic general"] {
+        match parse::<TestCommandArgs>(content, &fixtures()).await.unwrap() {
+            TestCommandArgs::Topic(args) => assert_eq!(args.channel.id.0, 20),
+            args => panic!("expected topic, got {:?}", args),
+        }
+    }
+}
+
+#[tokio::test]
+async fn author_default() {
+    match parse::<TestCommandArgs>("whoami", &fixtures()).await.unwrap() {
+        TestCommandArgs::Whoami(args) => assert_eq!(args.who.user.id.0, 2),
+        args => panic!("expected whoami, got {:?}", args),
+    }
+}
+
+#[tokio::test]
+async fn members_need_a_guild() {
+    let fixtures = fixtures().dm();
+    assert!(matches!(
+        value_error("kick bob", &fixtures).await,
+        ArgumentParseError::CannotParseInContext(_)
+    ));
+    assert!(matches!(
+        value_error("kick 2", &fixtures).await,
+        ArgumentParseError::CannotParseInContext(_)
+    ));
+}
+
+async fn parse_with<T: ArgumentType>(
+    resolver: &MockResolver,
+    value: &str,
+    strategy: ResolveStrategy,
+) -> Result<T, ArgumentParseError> {
+    let ctx = ArgumentParseContext::with_resolver(resolver, Some(Fixtures::GUILD_ID))
+        .with_strategy(strategy);
+    T::parse_argument(&ctx, ArgumentParseRaw { value: value.to_owned() }).await
+}
+
+#[tokio::test]
+async fn unknown_name_in_cached_guild() {
+    let resolver = fixtures().resolver();
+    let strategy = ResolveStrategy::CacheThenHttp;
+    let role = parse_with::<Role>(&resolver, "admins", strategy).await;
+    assert_eq!(role.unwrap_err(), ArgumentParseError::NotFound);
+    let channel = parse_with::<GuildChannel>(&resolver, "random", strategy).await;
+    assert_eq!(channel.unwrap_err(), ArgumentParseError::NotFound);
+    let member = parse_with::<Member>(&resolver, "nobody", strategy).await;
+    assert_eq!(member.unwrap_err(), ArgumentParseError::NotFound);
+    assert_eq!(resolver.http_requests(), 0);
+}
+
+#[tokio::test]
+async fn unknown_name_among_partial_members() {
+    let mut resolver = fixtures().resolver();
+    resolver.guilds.get_mut(&Fixtures::GUILD_ID).unwrap().member_count = 100;
+
+    let member = parse_with::<Member>(&resolver, "bob", ResolveStrategy::CacheThenHttp).await;
+    assert_eq!(member.unwrap().user.id.0, 2);
+    assert_eq!(resolver.http_requests(), 0);
+
+    let member = parse_with::<Member>(&resolver, "nobody", ResolveStrategy::CacheOnly).await;
+    assert_eq!(
+        member.unwrap_err(),
+        ArgumentParseError::CannotParseInContext("Member not in cache".to_owned())
+    );
+    assert_eq!(resolver.http_requests(), 0);
+
+    let member = parse_with::<Member>(&resolver, "nobody", ResolveStrategy::CacheThenHttp).await;
+    assert_eq!(member.unwrap_err(), ArgumentParseError::NotFound);
+    assert_eq!(resolver.http_requests(), 1);
+}
+
+#[tokio::test]
+async fn http_only_strategy() {
+    let resolver = fixtures().resolver();
+    let role = parse_with::<Role>(&resolver, "mods", ResolveStrategy::HttpOnly).await;
+    assert_eq!(role.unwrap().id.0, 10);
+    assert_eq!(resolver.http_requests(), 1);
+}
+
+async fn masskick(content: &str) -> Result<Vec<u64>, CommandArgumentsReadError> {
+    match parse::<TestCommandArgs>(content, &fixtures()).await? {
+        TestCommandArgs::Masskick(args) => {
+            Ok(args.targets.iter().map(|member| member.user.id.0).collect())
+        },
+        args => panic!("expected masskick, got {:?}", args),
+    }
+}
+
+#[tokio::test]
+async fn list_of_values() {
+    assert_eq!(masskick("masskick bob caz").await.unwrap(), [2, 3]);
+    assert_eq!(masskick("masskick 2 3 sam#0004").await.unwrap(), [2, 3, 4]);
+}
+
+#[tokio::test]
+async fn not_enough_values() {
+    for (content, count) in &[("masskick", 0), ("masskick bob", 1)] {
+        match masskick(content).await.unwrap_err() {
+            CommandArgumentsReadError::NotEnoughValues { position, name, min, given, .. } => {
+                assert_eq!(
+                    (position, name.as_str(), min, given),
+                    (1 + *count as u8, "targets", 2, *count)
+                );
+            },
+            error => panic!("expected not enough values, got {:?}", error),
+        }
+    }
+}
+
+#[tokio::test]
+async fn too_many_values() {
+    let content = "masskick 2 3 4 5";
+    match masskick(content).await.unwrap_err() {
+        CommandArgumentsReadError::TooManyValues { position, span, max, given, .. } => {
+            assert_eq!((position, max, given), (4, 3, 4));
+            assert_eq!(&content[span.unwrap()], "5");
+        },
+        error => panic!("expected too many values, got {:?}", error),
+    }
+}
+
+#[tokio::test]
+async fn first_invalid_value() {
+    match masskick("masskick bob nobody sam").await.unwrap_err() {
+        CommandArgumentsReadError::ValueParseError { position, value, .. } => {
+            assert_eq!((position, value.as_str()), (2, "nobody"));
+        },
+        error => panic!("expected a value error, got {:?}", error),
+    }
+}
+
+async fn notify(content: &str) -> Result<(Vec<u64>, Option<Vec<u64>>), CommandArgumentsReadError> {
+    match parse::<TestCommandArgs>(content, &fixtures()).await? {
+        TestCommandArgs::Notify(args) => Ok((
+            args.members.iter().map(|member| member.user.id.0).collect(),
+            args.roles.map(|roles| roles.iter().map(|role| role.id.0).collect()),
+        )),
+        args => panic!("expected notify, got {:?}", args),
+    }
+}
+
+async fn notify_error(content: &str) -> ArgumentParseError {
+    match notify(content).await.unwrap_err() {
+        CommandArgumentsReadError::ValueParseError { inner, .. } => inner,
+        error => panic!("expected a value error, got {:?}", error),
+    }
+}
+
+#[tokio::test]
+async fn list_separators() {
+    assert_eq!(notify("notify bob").await.unwrap(), (vec![2], None));
+    assert_eq!(notify("notify bob,caz").await.unwrap(), (vec![2, 3], None));
+    assert_eq!(
+        notify("notify \"bob , caz,\" <@&10>|mods").await.unwrap(),
+        (vec![2, 3], Some(vec![10, 10]))
+    );
+
+    // `|` doesn't separate elements of `List<T, Comma>`
+    match notify_error("notify bob|caz").await {
+        ArgumentParseError::InvalidElement { index: 0, value, .. } => assert_eq!(value, "bob|caz"),
+        error => panic!("expected an invalid element, got {:?}", error),
+    }
+    assert_eq!(notify_error("notify ,").await, ArgumentParseError::InvalidValueFormat);
+}
+
+#[tokio::test]
+async fn invalid_list_element_index() {
+    match notify_error("notify bob,,nobody").await {
+        ArgumentParseError::InvalidElement { index, value, inner } => {
+            assert_eq!(
+                (index, value.as_str(), *inner),
+                (2, "nobody", ArgumentParseError::NotFound)
+            );
+        },
+        error => panic!("expected an invalid element, got {:?}", error),
+    }
+}
+
+#[tokio::test]
+async fn context_and_message() {
+    let fixtures = fixtures().invoked_in(20);
+    let message = fixtures.message("!kick sam");
+    assert_eq!((message.author.id.0, message.author.name.as_str()), (2, "bob"));
+    assert_eq!((message.channel_id.0, message.guild_id), (20, Some(Fixtures::GUILD_ID)));
+    assert_eq!(message.content, "!kick sam");
+
+    let ctx = fixtures.context().await;
+    assert_eq!(ctx.cache.current_user_id().await, Fixtures::BOT_ID);
+    let member = ctx.cache.member(Fixtures::GUILD_ID, 3).await.unwrap();
+    assert_eq!(member.nick.as_deref(), Some("Caz"));
+    assert!(ctx.cache.role(Fixtures::GUILD_ID, 10).await.is_some());
+}